@@ -0,0 +1,365 @@
+use std::collections::HashMap;
+use std::mem::discriminant;
+
+use crate::{TgaColor, TgaError, TgaHeader, TgaImage, TgaImageState};
+use crate::TgaColor::*;
+use crate::TgaError::*;
+use crate::TgaImageType::*;
+
+impl TgaImage {
+    /// Tries to create a new color-mapped image with 8-bit indices of 0 into `palette`.
+    /// 
+    /// The color map's bit depth is the bit depth of the palette's colors.
+    /// 
+    /// # Errors
+    /// If `palette` is empty, has more than 256 colors, or has colors of different formats, returns `InvalidColorMap` error.
+    /// 
+    /// If the palette's colors are not `RGB16`, `RGB24`, or `RGBA`, returns `InvalidColorMap` error.
+    /// 
+    /// If `width` or `height` is zero, returns `InvalidSize` error.
+    pub fn new_color_mapped(width: u16, height: u16, palette: &[TgaColor]) -> Result<TgaImage, TgaError> {
+        // Ensure the palette has a single color map format
+        let first = palette.first().ok_or(InvalidColorMap)?;
+        if !is_entry_format(first) {
+            return Err(InvalidColorMap);
+        }
+        if palette.len() > 256 || palette.iter().any(|color| discriminant(color) != discriminant(first)) {
+            return Err(InvalidColorMap);
+        }
+
+        let mut image = TgaImage::new(ColorMappedImage, width, height, 8)?;
+        image.header.has_color_map = true;
+        image.header.color_map_size = palette.len() as u16;
+        image.header.color_map_bit_depth = first.bit_depth();
+        image.header.check_color_map()?;
+        image.color_map = palette.iter().flat_map(|color| color.as_slice().to_vec()).collect();
+
+        Ok(image)
+    }
+
+    /// Tries to get the color map entry that the pixel index `index` refers to.
+    /// 
+    /// Indices are offset by the header's `color_map_first_index`. Entries of a 15-bit color map are returned as opaque `RGB16` colors, as their attribute bit is unused.
+    /// 
+    /// # Errors
+    /// If the color map's bit depth does not match a color format, returns `InvalidColorMap` error.
+    /// 
+    /// If `index` does not refer to an entry in the color map, returns `InvalidColor` error.
+    pub fn color_map_entry(&self, index: u16) -> Result<TgaColor, TgaError> {
+        let entry_size = self.color_map_entry_size()?;
+        let entry = index.checked_sub(self.header.color_map_first_index).ok_or(InvalidColor)? as usize;
+        if entry >= self.header.color_map_size as usize {
+            return Err(InvalidColor);
+        }
+
+        let start = entry * entry_size;
+        let color = TgaColor::from_slice(self.color_map.get(start..start + entry_size).ok_or(InvalidColor)?)?;
+        match color {
+            // Ignore the attribute bit of 15-bit entries
+            TgaColor::RGB16([b, g]) if self.header.color_map_bit_depth == 15 => Ok(TgaColor::RGB16([b, g | 0x80])),
+            _ => Ok(color)
+        }
+    }
+
+    /// Tries to get the actual color of the pixel at (`x`, `y`).
+    /// 
+    /// For color-mapped images, the pixel's index is looked up in the color map, so the color has the color map's format. Other images return the same color as `get_pixel`. Use `resolve_color_map` to expand every pixel at once.
+    /// 
+    /// # Errors
+    /// If the coordinate is outside of the image, returns `InvalidCoordinate` error.
+    /// 
+    /// If the image's bit depth does not match a color format, returns `InvalidPixelDepth` error.
+    /// 
+    /// If the color map's bit depth does not match a color format, returns `InvalidColorMap` error.
+    /// 
+    /// If the pixel does not refer to an entry in the color map, returns `InvalidColor` error.
+    pub fn get_pixel_resolved(&self, x: u16, y: u16) -> Result<TgaColor, TgaError> {
+        let color = self.get_pixel(x, y)?;
+        if !self.header.image_type.is_color_mapped() {
+            return Ok(color);
+        }
+
+        self.color_map_entry(index_from_bytes(color.as_slice()))
+    }
+
+    /// Tries to replace every color map entry with the result of calling `f` on it.
+    /// 
+    /// Only the color map is changed; the pixel indices are left untouched. No entries are changed if any result is invalid.
+    /// 
+    /// # Errors
+    /// If the image is not color-mapped, returns `InvalidImageType` error.
+    /// 
+    /// If the color map's bit depth does not match a color format, returns `InvalidColorMap` error.
+    /// 
+    /// If `f` returns a color of a different format than the color map, returns `InvalidPixelDepth` error.
+    pub fn map_palette<F: FnMut(TgaColor) -> TgaColor>(&mut self, mut f: F) -> Result<(), TgaError> {
+        if !self.header.image_type.is_color_mapped() {
+            return Err(InvalidImageType);
+        }
+
+        // Map each entry into a new color map
+        let entry_size = self.color_map_entry_size()?;
+        let mut color_map = self.color_map.clone();
+        for entry in color_map.chunks_exact_mut(entry_size) {
+            let old = TgaColor::from_slice(entry)?;
+            let color = f(old);
+            if discriminant(&color) != discriminant(&old) {
+                return Err(InvalidPixelDepth);
+            }
+            entry.copy_from_slice(color.as_slice());
+        }
+        self.color_map = color_map;
+
+        Ok(())
+    }
+
+    /// Tries to create a true color copy of this color-mapped image by replacing each index with its color map entry.
+    /// 
+    /// The bit depth of the new image is the color map's bit depth. Run-length encoded images stay run-length encoded.
+    /// 
+    /// # Errors
+    /// If the image is not color-mapped, returns `InvalidImageType` error.
+    /// 
+    /// If the color map's bit depth does not match a color format, returns `InvalidColorMap` error.
+    /// 
+    /// If the indices are not 8 or 16 bits, returns `UnsupportedIndexSize` error.
+    /// 
+    /// If a pixel does not refer to an entry in the color map, returns `InvalidColor` error.
+    pub fn resolve_color_map(&self) -> Result<TgaImage, TgaError> {
+        if !self.header.image_type.is_color_mapped() {
+            return Err(InvalidImageType);
+        }
+
+        // Look up every index in storage order
+        let entry_size = self.color_map_entry_size()?;
+        let mut data = Vec::with_capacity(self.pixel_count() * entry_size);
+        for index in self.color_map_indices()? {
+            data.extend_from_slice(self.color_map_entry(index)?.as_slice());
+        }
+
+        // Create a true color header with the same dimensions and origin
+        let mut header = self.header;
+        header.image_type = if header.image_type.is_rle() { RleTrueColorImage } else { TrueColorImage };
+        header.has_color_map = false;
+        header.color_map_first_index = 0;
+        header.color_map_size = 0;
+        header.color_map_bit_depth = 0;
+        header.image_bit_depth = self.header.color_map_bit_depth;
+        header.descriptor &= 0xf0;
+        if header.image_bit_depth == 32 {
+            header.descriptor |= 8;
+        }
+
+        Ok(TgaImage {
+            header,
+            state: TgaImageState::Uncompressed,
+            id: self.id.clone(),
+            color_map: vec![].into_boxed_slice(),
+            data: data.into_boxed_slice(),
+            developer_tags: self.developer_tags.clone(),
+            extension: self.extension.clone()
+        })
+    }
+
+    /// Tries to create a color-mapped copy of this true color image with 8-bit indices.
+    /// 
+    /// This reverses `resolve_color_map`. If the image has at most 256 colors, the color map contains them exactly, in the order they are first stored. Otherwise, the low bits of every channel are dropped one at a time until at most 256 colors remain, and each color map entry is the rounded average of the pixels that share it. The color map has the image's bit depth, and run-length encoded images stay run-length encoded.
+    /// 
+    /// # Errors
+    /// If the image is not a 24/32-bit true color image, returns `InvalidImageType` error.
+    pub fn to_color_mapped(&self) -> Result<TgaImage, TgaError> {
+        if self.color_channels()? != 3 {
+            return Err(InvalidImageType);
+        }
+
+        // Drop low bits until the pixels fit in 256 entries
+        let byte_depth = self.byte_depth();
+        let mut shift = 0;
+        let (indices, sums, counts) = loop {
+            let mask = 0xffu8 << shift;
+            let mut entries = HashMap::new();
+            let mut indices = Vec::with_capacity(self.pixel_count());
+            let mut sums: Vec<[u32; 4]> = vec![];
+            let mut counts: Vec<u32> = vec![];
+            for pixel in self.data.chunks_exact(byte_depth) {
+                let mut key = [0; 4];
+                for (k, c) in key.iter_mut().zip(pixel) {
+                    *k = c & mask;
+                }
+                let entry = *entries.entry(key).or_insert_with(|| {
+                    sums.push([0; 4]);
+                    counts.push(0);
+                    sums.len() - 1
+                });
+                for (sum, c) in sums[entry].iter_mut().zip(pixel) {
+                    *sum += *c as u32;
+                }
+                counts[entry] += 1;
+                indices.push(entry as u8);
+                if sums.len() > 256 {
+                    break;
+                }
+            }
+            if sums.len() <= 256 {
+                break (indices, sums, counts);
+            }
+            shift += 1;
+        };
+
+        // Average the pixels of each entry into the color map
+        let mut color_map = Vec::with_capacity(sums.len() * byte_depth);
+        for (sum, count) in sums.iter().zip(counts) {
+            color_map.extend(sum[..byte_depth].iter().map(|s| ((s + count / 2) / count) as u8));
+        }
+
+        let mut header = self.header;
+        header.image_type = if header.image_type.is_rle() { RleColorMappedImage } else { ColorMappedImage };
+        header.has_color_map = true;
+        header.color_map_first_index = 0;
+        header.color_map_size = sums.len() as u16;
+        header.color_map_bit_depth = self.header.image_bit_depth;
+        header.image_bit_depth = 8;
+
+        Ok(TgaImage {
+            header,
+            state: TgaImageState::ColorMapped,
+            id: self.id.clone(),
+            color_map: color_map.into_boxed_slice(),
+            data: indices.into_boxed_slice(),
+            developer_tags: self.developer_tags.clone(),
+            extension: self.extension.clone()
+        })
+    }
+
+    /// Tries to remove the color map entries that no pixel refers to.
+    /// 
+    /// The remaining entries keep their order, every index is updated to match, and the first color map index is reset to 0.
+    /// 
+    /// # Errors
+    /// If the image is not color-mapped, returns `InvalidImageType` error.
+    /// 
+    /// If the color map's bit depth does not match a color format, returns `InvalidColorMap` error.
+    /// 
+    /// If the indices are not 8 or 16 bits, returns `UnsupportedIndexSize` error.
+    /// 
+    /// If a pixel does not refer to an entry in the color map, returns `InvalidColor` error.
+    pub fn compact_color_map(&mut self) -> Result<(), TgaError> {
+        if !self.header.image_type.is_color_mapped() {
+            return Err(InvalidImageType);
+        }
+
+        // Find which entries are used
+        let entry_size = self.color_map_entry_size()?;
+        let first_index = self.header.color_map_first_index;
+        let mut used = vec![false; self.header.color_map_size as usize];
+        for index in self.color_map_indices()? {
+            let entry = index.checked_sub(first_index).ok_or(InvalidColor)? as usize;
+            *used.get_mut(entry).ok_or(InvalidColor)? = true;
+        }
+
+        // Build the new color map and the mapping from old to new indices
+        let mut color_map = vec![];
+        let mut new_indices = vec![0; used.len()];
+        for (entry, _) in used.iter().enumerate().filter(|(_, &used)| used) {
+            new_indices[entry] = (color_map.len() / entry_size) as u16;
+            color_map.extend_from_slice(&self.color_map[entry * entry_size..(entry + 1) * entry_size]);
+        }
+
+        // Update the indices and header
+        let index_size = self.byte_depth();
+        for index in self.data.chunks_exact_mut(index_size) {
+            let new_index = new_indices[(index_from_bytes(index) - first_index) as usize].to_le_bytes();
+            index.copy_from_slice(&new_index[..index_size]);
+        }
+        self.header.color_map_first_index = 0;
+        self.header.color_map_size = (color_map.len() / entry_size) as u16;
+        self.color_map = color_map.into_boxed_slice();
+
+        Ok(())
+    }
+
+    /// Tries to discard the color map and reinterpret each 8-bit index as a greyscale value.
+    /// 
+    /// The image becomes a black and white image, keeping run-length encoding if it was run-length encoded. The pixel data is not changed.
+    /// 
+    /// # Errors
+    /// If the image is not color-mapped, returns `InvalidImageType` error.
+    /// 
+    /// If the indices are not 8 bits, returns `UnsupportedIndexSize` error.
+    pub fn drop_color_map(&mut self) -> Result<(), TgaError> {
+        if !self.header.image_type.is_color_mapped() {
+            return Err(InvalidImageType);
+        }
+        if self.header.image_bit_depth != 8 {
+            return Err(UnsupportedIndexSize);
+        }
+
+        self.header.image_type = if self.header.image_type.is_rle() { RleBlackAndWhiteImage } else { BlackAndWhiteImage };
+        self.header.has_color_map = false;
+        self.header.color_map_first_index = 0;
+        self.header.color_map_size = 0;
+        self.header.color_map_bit_depth = 0;
+        self.color_map = vec![].into_boxed_slice();
+        self.state = TgaImageState::Uncompressed;
+
+        Ok(())
+    }
+
+    /// Tries to create an iterator over the color map index of each pixel, in storage order.
+    /// 
+    /// # Errors
+    /// If the indices are not 8 or 16 bits, returns `UnsupportedIndexSize` error.
+    pub(crate) fn color_map_indices(&self) -> Result<impl Iterator<Item = u16> + '_, TgaError> {
+        if !matches!(self.header.image_bit_depth, 8 | 16) {
+            return Err(UnsupportedIndexSize);
+        }
+
+        Ok(self.data.chunks_exact(self.byte_depth()).map(index_from_bytes))
+    }
+
+    /// Returns whether this image's color map has the same entries, bit depth, and first index as `other`'s.
+    pub(crate) fn same_color_map(&self, other: &TgaImage) -> bool {
+        self.color_map == other.color_map
+            && self.header.color_map_bit_depth == other.header.color_map_bit_depth
+            && self.header.color_map_first_index == other.header.color_map_first_index
+    }
+
+    /// Tries to get the size of each color map entry in bytes.
+    pub(crate) fn color_map_entry_size(&self) -> Result<usize, TgaError> {
+        match self.header.color_map_bit_depth {
+            15 | 16 => Ok(2),
+            24 => Ok(3),
+            32 => Ok(4),
+            _ => Err(InvalidColorMap)
+        }
+    }
+}
+
+/// Returns whether `color` has a format that color map entries can use, which is `RGB16`, `RGB24`, or `RGBA`.
+pub(crate) fn is_entry_format(color: &TgaColor) -> bool {
+    matches!(color, RGB16(_) | RGB24(_) | RGBA(_))
+}
+
+/// Reads an 8-bit or little-endian 16-bit color map index from `bytes`.
+pub(crate) fn index_from_bytes(bytes: &[u8]) -> u16 {
+    match *bytes {
+        [low, high] => u16::from_le_bytes([low, high]),
+        _ => bytes[0] as u16
+    }
+}
+
+impl TgaHeader {
+    /// Ensures the header declares a usable color map.
+    /// 
+    /// A color map is required by color-mapped image types and must be declared with entries and a supported bit depth of 15, 16, 24, or 32.
+    pub(crate) fn check_color_map(&self) -> Result<(), TgaError> {
+        if self.has_color_map && self.color_map_size == 0 {
+            return Err(InvalidColorMap);
+        }
+        if (self.has_color_map || self.image_type.is_color_mapped()) && !matches!(self.color_map_bit_depth, 15 | 16 | 24 | 32) {
+            return Err(InvalidColorMap);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,262 @@
+use crate::{TgaColor, TgaError, TgaImage, TgaImageState};
+use crate::TgaColor::*;
+use crate::TgaError::*;
+use crate::TgaImageType::*;
+
+impl TgaImage {
+    /// Tries to convert a 24-bit true color image to a 16-bit true color image.
+    /// 
+    /// Each channel is truncated to its top 5 bits. The attribute bit of every pixel is cleared.
+    /// 
+    /// # Errors
+    /// If the image is not a 24-bit true color image, returns `InvalidImageType` error.
+    pub fn to_rgb16(&self) -> Result<TgaImage, TgaError> {
+        if !self.header.image_type.is_true_color() || self.header.image_bit_depth != 24 {
+            return Err(InvalidImageType);
+        }
+
+        let data: Vec<u8> = self.data.chunks_exact(3)
+            .flat_map(|pixel| pack_rgb16([pixel[0], pixel[1], pixel[2]]))
+            .collect();
+
+        Ok(self.with_pixel_data(16, data))
+    }
+
+    /// Tries to convert a 15/16-bit true color image to a 24-bit true color image.
+    /// 
+    /// Each 5-bit channel is expanded to 8 bits by replicating its top bits. The attribute bit is discarded.
+    /// 
+    /// # Errors
+    /// If the image is not a 15/16-bit true color image, returns `InvalidImageType` error.
+    pub fn rgb16_to_rgb24(&self) -> Result<TgaImage, TgaError> {
+        if !self.header.image_type.is_true_color() || !matches!(self.header.image_bit_depth, 15 | 16) {
+            return Err(InvalidImageType);
+        }
+
+        let data: Vec<u8> = self.data.chunks_exact(2)
+            .flat_map(|pixel| unpack_rgb16([pixel[0], pixel[1]]))
+            .collect();
+
+        Ok(self.with_pixel_data(24, data))
+    }
+
+    /// Tries to convert a 24-bit true color image to a 32-bit image where pixels of color `key` are transparent.
+    /// 
+    /// Pixels matching `key` get an alpha of 0 and every other pixel gets an alpha of 255. The color channels are kept as they are, and the result has 8 alpha bits.
+    /// 
+    /// # Errors
+    /// If the image is not a 24-bit true color image, returns `InvalidImageType` error.
+    /// 
+    /// If `key` is not an `RGB24` color, returns `InvalidColor` error.
+    pub fn color_key(&self, key: TgaColor) -> Result<TgaImage, TgaError> {
+        if !self.header.image_type.is_true_color() || self.header.image_bit_depth != 24 {
+            return Err(InvalidImageType);
+        }
+        if !matches!(key, RGB24(_)) {
+            return Err(InvalidColor);
+        }
+
+        let data: Vec<u8> = self.data.chunks_exact(3)
+            .flat_map(|pixel| {
+                let alpha = if pixel == key.as_slice() { 0 } else { 255 };
+                [pixel[0], pixel[1], pixel[2], alpha]
+            })
+            .collect();
+
+        let mut image = self.with_pixel_data(32, data);
+        image.header.set_alpha_bits(8)?;
+        Ok(image)
+    }
+
+    /// Tries to extract one channel of this image into a greyscale image.
+    /// 
+    /// Channels are numbered in storage order: 0 is blue, 1 is green, 2 is red, and 3 is alpha. Greyscale images only have channel 0.
+    /// 
+    /// # Errors
+    /// If the image is not an 8-bit greyscale or 24/32-bit true color image, returns `InvalidImageType` error.
+    /// 
+    /// If `channel` is not less than the image's number of channels, returns `InvalidColor` error.
+    pub fn extract_channel(&self, channel: usize) -> Result<TgaImage, TgaError> {
+        self.color_channels()?;
+        let byte_depth = self.byte_depth();
+        if channel >= byte_depth {
+            return Err(InvalidColor);
+        }
+
+        let data: Vec<u8> = self.data.chunks_exact(byte_depth)
+            .map(|pixel| pixel[channel])
+            .collect();
+
+        Ok(self.with_greyscale_data(data))
+    }
+
+    /// Tries to merge separate greyscale channel images into one true color image.
+    /// 
+    /// This reverses `extract_channel`. The result is a 32-bit image with 8 alpha bits if `a` is given, and a 24-bit image otherwise. Pixels are merged by coordinate, so the inputs may have different origins.
+    /// 
+    /// # Errors
+    /// If any input is not an 8-bit greyscale image, returns `InvalidImageType` error.
+    /// 
+    /// If the inputs' dimensions differ, returns `InvalidSize` error.
+    pub fn from_channels(r: &TgaImage, g: &TgaImage, b: &TgaImage, a: Option<&TgaImage>) -> Result<TgaImage, TgaError> {
+        // Channels in storage order
+        let mut channels = vec![b, g, r];
+        channels.extend(a);
+
+        // Ensure every channel is a greyscale image of the same size
+        for channel in &channels {
+            if !channel.header.image_type.is_greyscale() || channel.header.image_bit_depth != 8 {
+                return Err(InvalidImageType);
+            }
+            if channel.header.width != r.header.width || channel.header.height != r.header.height {
+                return Err(InvalidSize);
+            }
+        }
+
+        let mut image = TgaImage::new(TrueColorImage, r.header.width, r.header.height, channels.len() as u8 * 8)?;
+        if a.is_some() {
+            image.header.set_alpha_bits(8)?;
+        }
+        for y in 0..image.header.height {
+            for x in 0..image.header.width {
+                let dest = image.pixel_offset(x, y);
+                for (i, channel) in channels.iter().enumerate() {
+                    image.data[dest + i] = channel.data[channel.pixel_offset(x, y)];
+                }
+            }
+        }
+
+        Ok(image)
+    }
+
+    /// Tries to swap the first and third channels of every pixel.
+    /// 
+    /// This converts between the BGR order used by TGA files and the RGB order used by most other buffers. The green and alpha channels are left in place.
+    /// 
+    /// # Errors
+    /// If the image is not a 24/32-bit true color image, returns `InvalidImageType` error.
+    pub fn swap_rb(&mut self) -> Result<(), TgaError> {
+        if self.color_channels()? != 3 {
+            return Err(InvalidImageType);
+        }
+
+        let byte_depth = self.byte_depth();
+        for pixel in self.data.chunks_exact_mut(byte_depth) {
+            pixel.swap(0, 2);
+        }
+
+        Ok(())
+    }
+
+    /// Tries to convert the image to tightly packed RGBA pixels, starting from the top-left corner.
+    /// 
+    /// Greyscale pixels are replicated to every color channel, 16-bit pixels are expanded to 8 bits per channel, and color-mapped pixels are looked up in the color map. Pixels without alpha are opaque, and the attribute bit of 16-bit pixels or alpha byte of 16-bit greyscale pixels is only used as alpha if the descriptor declares alpha bits.
+    /// 
+    /// # Errors
+    /// If a color-mapped pixel does not refer to an entry in the color map, returns `InvalidColor` error.
+    /// 
+    /// If the color map's bit depth does not match a color format, returns `InvalidColorMap` error.
+    /// 
+    /// If the image's bit depth does not match a color format, returns `InvalidPixelDepth` error.
+    pub fn as_rgba_vec(&self) -> Result<Vec<u8>, TgaError> {
+        let mut rgba = Vec::with_capacity(self.pixel_count() * 4);
+        for y in 0..self.header.height {
+            for x in 0..self.header.width {
+                rgba.extend_from_slice(&self.pixel_rgba(x, y)?);
+            }
+        }
+
+        Ok(rgba)
+    }
+
+    /// Tries to check whether every pixel has an alpha of 255.
+    /// 
+    /// Alpha is read as described in `as_rgba_vec`, so images without alpha are always fully opaque. Color-mapped pixels use the alpha of their color map entry.
+    /// 
+    /// # Errors
+    /// If a color-mapped pixel does not refer to an entry in the color map, returns `InvalidColor` error.
+    /// 
+    /// If the color map's bit depth does not match a color format, returns `InvalidColorMap` error.
+    /// 
+    /// If the image's bit depth does not match a color format, returns `InvalidPixelDepth` error.
+    pub fn is_fully_opaque(&self) -> Result<bool, TgaError> {
+        for y in 0..self.header.height {
+            for x in 0..self.header.width {
+                if self.pixel_rgba(x, y)?[3] != 255 {
+                    return Ok(false);
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Tries to check whether any pixel has an alpha below 255.
+    /// 
+    /// This is the opposite of `is_fully_opaque`, and returns the same errors.
+    pub fn has_transparency(&self) -> Result<bool, TgaError> {
+        Ok(!self.is_fully_opaque()?)
+    }
+
+    /// Tries to get the color of the pixel at (`x`, `y`) in RGBA order, as described in `as_rgba_vec`.
+    pub(crate) fn pixel_rgba(&self, x: u16, y: u16) -> Result<[u8; 4], TgaError> {
+        let color = self.get_pixel_resolved(x, y)?;
+
+        // The attribute bit or alpha byte is only alpha if the descriptor declares alpha bits
+        let mut rgba = color.to_rgba8();
+        if let RGB16(_) | GreyscaleAlpha(_) = color {
+            if self.header.image_type.is_color_mapped() || self.header.alpha_bits() == 0 {
+                rgba[3] = 255;
+            }
+        }
+
+        Ok(rgba)
+    }
+
+    /// Returns a copy of this image as an 8-bit `BlackAndWhiteImage` with new pixel data.
+    fn with_greyscale_data(&self, data: Vec<u8>) -> TgaImage {
+        let mut image = self.with_pixel_data(8, data);
+        image.header.image_type = BlackAndWhiteImage;
+        image.header.has_color_map = false;
+        image.header.color_map_first_index = 0;
+        image.header.color_map_size = 0;
+        image.header.color_map_bit_depth = 0;
+        image.color_map = vec![].into_boxed_slice();
+        image.state = TgaImageState::Uncompressed;
+        image
+    }
+
+    /// Returns a copy of this image with new pixel data of a different bit depth.
+    /// 
+    /// The descriptor's alpha bits are cleared since the new pixel format has no alpha.
+    fn with_pixel_data(&self, bit_depth: u8, data: Vec<u8>) -> TgaImage {
+        let mut header = self.header;
+        header.image_bit_depth = bit_depth;
+        header.descriptor &= 0xf0;
+
+        TgaImage {
+            header,
+            state: self.state,
+            id: self.id.clone(),
+            color_map: self.color_map.clone(),
+            data: data.into_boxed_slice(),
+            developer_tags: self.developer_tags.clone(),
+            extension: self.extension.clone()
+        }
+    }
+}
+
+/// Packs a BGR color into a little-endian 5-5-5 `RGB16` color.
+pub(crate) fn pack_rgb16(bgr: [u8; 3]) -> [u8; 2] {
+    let [b, g, r] = bgr.map(|c| (c >> 3) as u16);
+    ((r << 10) | (g << 5) | b).to_le_bytes()
+}
+
+/// Expands a little-endian 5-5-5 `RGB16` color into a BGR color.
+pub(crate) fn unpack_rgb16(bytes: [u8; 2]) -> [u8; 3] {
+    let value = u16::from_le_bytes(bytes);
+    [value, value >> 5, value >> 10].map(|c| {
+        let c = (c & 0x1f) as u8;
+        (c << 3) | (c >> 2)
+    })
+}
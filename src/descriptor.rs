@@ -0,0 +1,70 @@
+use crate::{TgaError, TgaHeader, DESCRIPTOR_RESERVED, DESCRIPTOR_RIGHT, DESCRIPTOR_TOP};
+use crate::TgaError::*;
+
+/// The named fields of a TGA header's image descriptor byte.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TgaDescriptor {
+    /// The number of attribute (alpha) bits per pixel, stored in bits 0-3.
+    pub alpha_bits: u8,
+    /// Whether pixels are stored from right to left, stored in bit 4.
+    pub right_origin: bool,
+    /// Whether rows are stored from top to bottom, stored in bit 5.
+    pub top_origin: bool,
+}
+
+impl TgaDescriptor {
+    /// Tries to read the descriptor fields from a descriptor byte.
+    /// 
+    /// # Errors
+    /// If either of the reserved bits 6-7 is set, returns `InvalidArgument` error.
+    pub fn from_u8(value: u8) -> Result<TgaDescriptor, TgaError> {
+        if value & DESCRIPTOR_RESERVED != 0 {
+            return Err(InvalidArgument);
+        }
+
+        Ok(TgaDescriptor {
+            alpha_bits: value & 0x0f,
+            right_origin: value & DESCRIPTOR_RIGHT != 0,
+            top_origin: value & DESCRIPTOR_TOP != 0
+        })
+    }
+
+    /// Returns the descriptor byte for these fields.
+    /// 
+    /// Only the low 4 bits of `alpha_bits` are kept, and the reserved bits are always zero.
+    pub fn to_u8(&self) -> u8 {
+        let mut value = self.alpha_bits & 0x0f;
+        if self.right_origin {
+            value |= DESCRIPTOR_RIGHT;
+        }
+        if self.top_origin {
+            value |= DESCRIPTOR_TOP;
+        }
+        value
+    }
+}
+
+impl TgaHeader {
+    /// Tries to get the named fields of the descriptor.
+    /// 
+    /// # Errors
+    /// If either of the reserved bits 6-7 is set, returns `InvalidArgument` error.
+    pub fn descriptor_flags(&self) -> Result<TgaDescriptor, TgaError> {
+        TgaDescriptor::from_u8(self.descriptor)
+    }
+
+    /// Tries to set the descriptor from its named fields.
+    /// 
+    /// Changing the origin fields does not reorder the pixel data; use `TgaImage::set_top_origin` to keep the image looking the same.
+    /// 
+    /// # Errors
+    /// If `flags.alpha_bits` does not fit the image's bit depth, returns `InvalidPixelDepth` error.
+    pub fn set_descriptor_flags(&mut self, flags: TgaDescriptor) -> Result<(), TgaError> {
+        let mut header = *self;
+        header.descriptor = flags.to_u8() & 0xf0;
+        header.set_alpha_bits(flags.alpha_bits)?;
+        *self = header;
+
+        Ok(())
+    }
+}
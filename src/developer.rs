@@ -0,0 +1,111 @@
+use std::convert::TryInto;
+
+use crate::{TgaError, TgaImage};
+use crate::TgaError::*;
+
+/// The size of a developer directory entry in bytes.
+const ENTRY_SIZE: usize = 10;
+
+/// A tagged block of application-specific data from a TGA 2.0 developer area.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeveloperTag {
+    pub id: u16,
+    pub data: Vec<u8>,
+}
+
+impl TgaImage {
+    /// Returns the data of the developer tag with the id `id`.
+    /// 
+    /// Returns `None` if the image has no such tag.
+    pub fn developer_tag(&self, id: u16) -> Option<&[u8]> {
+        self.developer_tags.iter().find(|tag| tag.id == id).map(|tag| &tag.data[..])
+    }
+
+    /// Sets the data of the developer tag with the id `id`, replacing an existing tag or adding a new one.
+    /// 
+    /// Developer tags are written to a developer area by `to_bytes`, followed by a TGA 2.0 footer.
+    pub fn set_developer_tag(&mut self, id: u16, data: Vec<u8>) {
+        match self.developer_tags.iter_mut().find(|tag| tag.id == id) {
+            Some(tag) => tag.data = data,
+            None => self.developer_tags.push(DeveloperTag { id, data })
+        }
+    }
+
+    /// Removes the developer tag with the id `id`, returning it.
+    /// 
+    /// Returns `None` if the image has no such tag.
+    pub fn remove_developer_tag(&mut self, id: u16) -> Option<DeveloperTag> {
+        let index = self.developer_tags.iter().position(|tag| tag.id == id)?;
+        Some(self.developer_tags.remove(index))
+    }
+}
+
+/// Appends the data of every tag in `tags` to `buf`, followed by the developer directory, and returns the directory's offset.
+/// 
+/// Offsets are relative to the start of `buf`, which must be the start of the TGA file.
+pub(crate) fn write_developer_area(buf: &mut Vec<u8>, tags: &[DeveloperTag]) -> u32 {
+    // Write the data of each tag before the directory
+    let mut entries = Vec::with_capacity(tags.len());
+    for tag in tags {
+        entries.push((tag.id, buf.len() as u32, tag.data.len() as u32));
+        buf.extend_from_slice(&tag.data);
+    }
+
+    let offset = buf.len() as u32;
+    buf.extend_from_slice(&(tags.len() as u16).to_le_bytes());
+    for (id, data_offset, data_size) in entries {
+        buf.extend_from_slice(&id.to_le_bytes());
+        buf.extend_from_slice(&data_offset.to_le_bytes());
+        buf.extend_from_slice(&data_size.to_le_bytes());
+    }
+
+    offset
+}
+
+/// Tries to read the developer directory at `offset` in `buf` and the data of each tag it lists.
+/// 
+/// # Errors
+/// If the directory or the data of any tag does not fit in `buf`, returns `InvalidSize` error.
+pub(crate) fn parse_developer_area(buf: &[u8], offset: usize) -> Result<Vec<DeveloperTag>, TgaError> {
+    // Read the number of tags
+    let count_buf = buf.get(offset..offset + 2).ok_or(InvalidSize)?;
+    let count = u16::from_le_bytes(count_buf.try_into().map_err(|_| {InvalidSize})?) as usize;
+
+    // Read each directory entry and the data it points to
+    let mut tags = Vec::with_capacity(count);
+    let mut idx = offset + 2;
+    for _ in 0..count {
+        let entry = buf.get(idx..idx + ENTRY_SIZE).ok_or(InvalidSize)?;
+        let id = u16::from_le_bytes(entry[0..2].try_into().map_err(|_| {InvalidSize})?);
+        let data_offset = u32::from_le_bytes(entry[2..6].try_into().map_err(|_| {InvalidSize})?) as usize;
+        let data_size = u32::from_le_bytes(entry[6..10].try_into().map_err(|_| {InvalidSize})?) as usize;
+        let data = buf.get(data_offset..data_offset.saturating_add(data_size)).ok_or(InvalidSize)?;
+        tags.push(DeveloperTag {
+            id,
+            data: data.to_vec()
+        });
+        idx += ENTRY_SIZE;
+    }
+
+    Ok(tags)
+}
+
+/// Tries to find the end of the developer area at `offset` in `buf`, including the data of every tag.
+/// 
+/// # Errors
+/// If the directory does not fit in `buf`, returns `InvalidSize` error.
+pub(crate) fn developer_area_end(buf: &[u8], offset: usize) -> Result<usize, TgaError> {
+    let count_buf = buf.get(offset..offset + 2).ok_or(InvalidSize)?;
+    let count = u16::from_le_bytes(count_buf.try_into().map_err(|_| {InvalidSize})?) as usize;
+
+    // The area ends after the directory or the last tag's data, whichever is later
+    let mut end = offset + 2 + count * ENTRY_SIZE;
+    let directory = buf.get(offset + 2..end).ok_or(InvalidSize)?;
+    for entry in directory.chunks_exact(ENTRY_SIZE) {
+        let data_offset = u32::from_le_bytes(entry[2..6].try_into().map_err(|_| {InvalidSize})?) as usize;
+        let data_size = u32::from_le_bytes(entry[6..10].try_into().map_err(|_| {InvalidSize})?) as usize;
+        end = end.max(data_offset + data_size);
+    }
+
+    Ok(end)
+}
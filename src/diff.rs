@@ -0,0 +1,62 @@
+use crate::{TgaError, TgaImage};
+use crate::TgaError::*;
+
+/// The differences between two images of the same size.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ImageDiff {
+    /// The number of pixels with at least one differing channel.
+    pub differing_pixels: usize,
+    /// The largest difference between any two corresponding channels.
+    pub max_channel_diff: u8,
+    /// The peak signal-to-noise ratio in decibels, or infinity if the images are identical.
+    pub psnr: f64,
+}
+
+impl TgaImage {
+    /// Tries to compare this image's pixels with `other`'s pixels.
+    /// 
+    /// Pixels are compared at the same displayed coordinate, so images stored with different origins can be compared.
+    /// 
+    /// # Errors
+    /// If the images' dimensions or bit depths differ, returns `InvalidSize` error.
+    pub fn diff(&self, other: &TgaImage) -> Result<ImageDiff, TgaError> {
+        // Ensure the images have the same layout
+        if self.header.width != other.header.width || self.header.height != other.header.height
+            || self.header.image_bit_depth != other.header.image_bit_depth {
+            return Err(InvalidSize);
+        }
+
+        // Compare each pixel channel by channel
+        let mut differing_pixels = 0;
+        let mut max_channel_diff = 0;
+        let mut squared_error = 0u64;
+        let byte_depth = self.byte_depth();
+        let coordinates = (0..self.header.height).flat_map(|y| (0..self.header.width).map(move |x| (x, y)));
+        for (x, y) in coordinates {
+            let (a, b) = (self.pixel_offset(x, y), other.pixel_offset(x, y));
+            let (a, b) = (&self.data[a..a + byte_depth], &other.data[b..b + byte_depth]);
+            if a != b {
+                differing_pixels += 1;
+            }
+            for (a, b) in a.iter().zip(b) {
+                let channel_diff = a.abs_diff(*b);
+                max_channel_diff = max_channel_diff.max(channel_diff);
+                squared_error += channel_diff as u64 * channel_diff as u64;
+            }
+        }
+
+        // Compute the PSNR from the mean squared error
+        let psnr = if squared_error == 0 {
+            f64::INFINITY
+        } else {
+            let mse = squared_error as f64 / self.data.len() as f64;
+            10.0 * (255.0 * 255.0 / mse).log10()
+        };
+
+        Ok(ImageDiff {
+            differing_pixels,
+            max_channel_diff,
+            psnr
+        })
+    }
+}
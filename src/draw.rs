@@ -0,0 +1,310 @@
+use crate::{color_map, TgaColor, TgaError, TgaImage};
+use crate::TgaColor::*;
+use crate::TgaError::*;
+use crate::TgaImageType::*;
+
+impl TgaImage {
+    /// Tries to create a checkerboard image alternating between `a` and `b` every `cell` pixels.
+    /// 
+    /// The top-left cell is `a`. The image is a 24-bit true color image for `RGB24` colors, or a 32-bit true color image with 8 alpha bits for `RGBA` colors.
+    /// 
+    /// # Errors
+    /// If the colors are not both `RGB24` or both `RGBA`, returns `InvalidColor` error.
+    /// 
+    /// If `width`, `height`, or `cell` is zero, returns `InvalidSize` error.
+    pub fn checker(width: u16, height: u16, cell: u16, a: TgaColor, b: TgaColor) -> Result<TgaImage, TgaError> {
+        let bit_depth = match (a, b) {
+            (RGB24(_), RGB24(_)) => 24,
+            (RGBA(_), RGBA(_)) => 32,
+            _ => return Err(InvalidColor)
+        };
+        if cell == 0 {
+            return Err(InvalidSize);
+        }
+
+        let mut image = TgaImage::new(TrueColorImage, width, height, bit_depth)?;
+        if bit_depth == 32 {
+            image.header.set_alpha_bits(8)?;
+        }
+        for y in 0..height {
+            for x in 0..width {
+                let color = if (x / cell + y / cell) % 2 == 1 { b } else { a };
+                let start = image.pixel_offset(x, y);
+                image.data[start..start + color.as_slice().len()].copy_from_slice(color.as_slice());
+            }
+        }
+
+        Ok(image)
+    }
+
+    /// Tries to fill the region of same-colored pixels connected to (`x`, `y`) with `color`.
+    /// 
+    /// Pixels are connected to their four horizontal and vertical neighbors.
+    /// 
+    /// # Errors
+    /// If the coordinate is outside of the image, returns `InvalidCoordinate` error.
+    /// 
+    /// If the color is invalid for this image, returns `InvalidColor` error.
+    /// 
+    /// If the color's bit depth does not match this image, returns `InvalidPixelDepth` error.
+    pub fn flood_fill(&mut self, x: u16, y: u16, color: TgaColor) -> Result<(), TgaError> {
+        // Ensure that the pixel coordinate is valid for this image
+        let start = self.byte_offset(x, y).ok_or(InvalidCoordinate)?;
+
+        self.check_color(color)?;
+
+        // Nothing to do if the region already has the fill color
+        let byte_depth = self.byte_depth();
+        let target = self.data[start..start + byte_depth].to_vec();
+        if target == color.as_slice() {
+            return Ok(());
+        }
+
+        // Fill pixels using an explicit stack to avoid deep recursion
+        let mut stack = vec![(x, y)];
+        while let Some((x, y)) = stack.pop() {
+            let start = self.pixel_offset(x, y);
+            let pixel = &mut self.data[start..start + byte_depth];
+            if pixel != &target[..] {
+                continue;
+            }
+            pixel.copy_from_slice(color.as_slice());
+
+            if x > 0 {
+                stack.push((x - 1, y));
+            }
+            if x + 1 < self.header.width {
+                stack.push((x + 1, y));
+            }
+            if y > 0 {
+                stack.push((x, y - 1));
+            }
+            if y + 1 < self.header.height {
+                stack.push((x, y + 1));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Tries to draw the one pixel thick outline of the `w`x`h` rectangle at (`x`, `y`) with `color`.
+    /// 
+    /// The pixels inside of the outline are left unchanged. A rectangle with a zero width or height draws nothing.
+    /// 
+    /// # Errors
+    /// If the rectangle is not fully inside the image, returns `InvalidCoordinate` error.
+    /// 
+    /// If the color is invalid for this image, returns `InvalidColor` error.
+    /// 
+    /// If the color's bit depth does not match this image, returns `InvalidPixelDepth` error.
+    pub fn draw_rect(&mut self, x: u16, y: u16, w: u16, h: u16, color: TgaColor) -> Result<(), TgaError> {
+        if w == 0 || h == 0 {
+            return Ok(());
+        }
+        self.check_rect(x, y, w, h)?;
+        self.check_color(color)?;
+
+        // Draw the top and bottom edges, then the left and right edges
+        let right = x + w - 1;
+        let bottom = y + h - 1;
+        for px in x..=right {
+            self.set_pixel(px, y, color)?;
+            self.set_pixel(px, bottom, color)?;
+        }
+        for py in y..=bottom {
+            self.set_pixel(x, py, color)?;
+            self.set_pixel(right, py, color)?;
+        }
+
+        Ok(())
+    }
+
+    /// Tries to replace every pixel of color `from` with `to`, returning the number of pixels changed.
+    /// 
+    /// For color-mapped images, `from` and `to` are color map entries. Every matching entry is replaced, and the pixels that refer to those entries are counted.
+    /// 
+    /// # Errors
+    /// If either color is invalid for this image, returns `InvalidColor` error.
+    /// 
+    /// If either color's bit depth does not match this image or its color map, returns `InvalidPixelDepth` error.
+    /// 
+    /// If the color map's bit depth does not match a color format, returns `InvalidColorMap` error.
+    pub fn replace_color(&mut self, from: TgaColor, to: TgaColor) -> Result<usize, TgaError> {
+        if self.header.image_type.is_color_mapped() {
+            // Ensure both colors match the color map format
+            if !color_map::is_entry_format(&from) || !color_map::is_entry_format(&to) {
+                return Err(InvalidColor);
+            }
+            let entry_size = self.color_map_entry_size()?;
+            if from.as_slice().len() != entry_size || to.as_slice().len() != entry_size {
+                return Err(InvalidPixelDepth);
+            }
+
+            // Replace the matching entries, then count the pixels that refer to them
+            let mut replaced = vec![false; self.header.color_map_size as usize];
+            for (entry, color) in self.color_map.chunks_exact_mut(entry_size).enumerate() {
+                if color == from.as_slice() {
+                    color.copy_from_slice(to.as_slice());
+                    replaced[entry] = true;
+                }
+            }
+            let first_index = self.header.color_map_first_index;
+            let count = self.color_map_indices()?
+                .filter_map(|index| index.checked_sub(first_index))
+                .filter(|&entry| replaced.get(entry as usize) == Some(&true))
+                .count();
+            return Ok(count);
+        }
+
+        self.check_color(from)?;
+        self.check_color(to)?;
+
+        let byte_depth = self.byte_depth();
+        let mut count = 0;
+        for pixel in self.data.chunks_exact_mut(byte_depth) {
+            if pixel == from.as_slice() {
+                pixel.copy_from_slice(to.as_slice());
+                count += 1;
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Tries to copy the `w`x`h` rectangle at (`src_x`, `src_y`) to (`dest_x`, `dest_y`).
+    /// 
+    /// The source and destination rectangles may overlap.
+    /// 
+    /// # Errors
+    /// If either rectangle is not fully inside the image, returns `InvalidCoordinate` error.
+    pub fn copy_region(&mut self, src_x: u16, src_y: u16, w: u16, h: u16, dest_x: u16, dest_y: u16) -> Result<(), TgaError> {
+        // Ensure both rectangles are inside the image
+        self.check_rect(src_x, src_y, w, h)?;
+        self.check_rect(dest_x, dest_y, w, h)?;
+
+        let byte_depth = self.byte_depth();
+        let overlaps = src_x < dest_x + w && dest_x < src_x + w && src_y < dest_y + h && dest_y < src_y + h;
+        if overlaps {
+            // Copy the source region to a temporary buffer first to avoid overwriting it mid-copy
+            let mut region = Vec::with_capacity(w as usize * h as usize * byte_depth);
+            for y in 0..h {
+                for x in 0..w {
+                    let start = self.pixel_offset(src_x + x, src_y + y);
+                    region.extend_from_slice(&self.data[start..start + byte_depth]);
+                }
+            }
+            let mut pixels = region.chunks_exact(byte_depth);
+            for y in 0..h {
+                for x in 0..w {
+                    let start = self.pixel_offset(dest_x + x, dest_y + y);
+                    if let Some(pixel) = pixels.next() {
+                        self.data[start..start + byte_depth].copy_from_slice(pixel);
+                    }
+                }
+            }
+        } else {
+            for y in 0..h {
+                for x in 0..w {
+                    let src = self.pixel_offset(src_x + x, src_y + y);
+                    let dest = self.pixel_offset(dest_x + x, dest_y + y);
+                    self.data.copy_within(src..src + byte_depth, dest);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Tries to fill the image by repeating `tile` from the top-left corner.
+    /// 
+    /// Tiles that do not fit are cut off at the image's edges.
+    /// 
+    /// # Errors
+    /// If `tile`'s image type differs from this image, ignoring run-length encoding, returns `InvalidImageType` error.
+    /// 
+    /// If `tile`'s bit depth differs from this image, returns `InvalidPixelDepth` error.
+    /// 
+    /// If the images are color-mapped and their color maps differ, returns `InvalidColorMap` error.
+    /// 
+    /// If `tile` has no pixels, returns `InvalidSize` error.
+    pub fn tile(&mut self, tile: &TgaImage) -> Result<(), TgaError> {
+        if tile.header.image_type.base_type() != self.header.image_type.base_type() {
+            return Err(InvalidImageType);
+        }
+        if tile.header.image_bit_depth != self.header.image_bit_depth {
+            return Err(InvalidPixelDepth);
+        }
+        if self.header.image_type.is_color_mapped() && !tile.same_color_map(self) {
+            return Err(InvalidColorMap);
+        }
+        if tile.header.width == 0 || tile.header.height == 0 {
+            return Err(InvalidSize);
+        }
+
+        // Copy each pixel from the wrapped tile coordinate
+        let byte_depth = self.byte_depth();
+        for y in 0..self.header.height {
+            for x in 0..self.header.width {
+                let src = tile.pixel_offset(x % tile.header.width, y % tile.header.height);
+                let dest = self.pixel_offset(x, y);
+                self.data[dest..dest + byte_depth].copy_from_slice(&tile.data[src..src + byte_depth]);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Tries to add the color channels of `src` to this image, with `src`'s top-left corner at (`x`, `y`).
+    /// 
+    /// Channels are added with saturating arithmetic, and the alpha channel is left unchanged. Parts of `src` outside of this image are clipped.
+    /// 
+    /// # Errors
+    /// If the images are not both greyscale or both true color, or are not 8-bit greyscale or 24/32-bit true color images, returns `InvalidImageType` error.
+    /// 
+    /// If the images' bit depths differ, returns `InvalidPixelDepth` error.
+    pub fn blend_add(&mut self, src: &TgaImage, x: u16, y: u16) -> Result<(), TgaError> {
+        self.blend_with(src, x, y, u8::saturating_add)
+    }
+
+    /// Tries to subtract the color channels of `src` from this image, with `src`'s top-left corner at (`x`, `y`).
+    /// 
+    /// Channels are subtracted with saturating arithmetic, and the alpha channel is left unchanged. Parts of `src` outside of this image are clipped.
+    /// 
+    /// # Errors
+    /// If the images are not both greyscale or both true color, or are not 8-bit greyscale or 24/32-bit true color images, returns `InvalidImageType` error.
+    /// 
+    /// If the images' bit depths differ, returns `InvalidPixelDepth` error.
+    pub fn blend_sub(&mut self, src: &TgaImage, x: u16, y: u16) -> Result<(), TgaError> {
+        self.blend_with(src, x, y, u8::saturating_sub)
+    }
+
+    /// Tries to combine each color channel of this image with the overlapping channel of `src` using `op`.
+    fn blend_with<F: Fn(u8, u8) -> u8>(&mut self, src: &TgaImage, x: u16, y: u16, op: F) -> Result<(), TgaError> {
+        // Ensure the images have compatible formats
+        let channels = self.color_channels()?;
+        src.color_channels()?;
+        if self.header.image_type.base_type() != src.header.image_type.base_type() {
+            return Err(InvalidImageType);
+        }
+        if self.header.image_bit_depth != src.header.image_bit_depth {
+            return Err(InvalidPixelDepth);
+        }
+
+        // Clip the source to this image
+        let width = src.header.width.min(self.header.width.saturating_sub(x));
+        let height = src.header.height.min(self.header.height.saturating_sub(y));
+
+        for sy in 0..height {
+            for sx in 0..width {
+                let src_start = src.pixel_offset(sx, sy);
+                let dest_start = self.pixel_offset(x + sx, y + sy);
+                for channel in 0..channels {
+                    let dest = &mut self.data[dest_start + channel];
+                    *dest = op(*dest, src.data[src_start + channel]);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,239 @@
+use std::convert::TryInto;
+
+use crate::{TgaError, TgaImage};
+use crate::TgaError::*;
+
+/// The size of a TGA 2.0 extension area in bytes.
+pub(crate) const EXTENSION_SIZE: usize = 495;
+
+/// The offset and size of the author name field in the extension area.
+const AUTHOR_NAME: (usize, usize) = (2, 41);
+
+/// The offset of the author comments field in the extension area.
+const AUTHOR_COMMENTS_OFFSET: usize = 43;
+
+/// The number of lines in the author comments field.
+const COMMENT_LINES: usize = 4;
+
+/// The size of each line in the author comments field, including its null terminator.
+const COMMENT_LINE_SIZE: usize = 81;
+
+/// The offset of the date and time stamp in the extension area.
+const DATE_TIME_OFFSET: usize = 367;
+
+/// The offset and size of the job name field in the extension area.
+const JOB_NAME: (usize, usize) = (379, 41);
+
+/// The offset of the job time in the extension area.
+const JOB_TIME_OFFSET: usize = 420;
+
+/// The offset and size of the software id field in the extension area.
+const SOFTWARE_ID: (usize, usize) = (426, 41);
+
+/// The offset of the software version number in the extension area.
+const SOFTWARE_VERSION_OFFSET: usize = 467;
+
+/// The offset of the gamma numerator and denominator in the extension area.
+const GAMMA_OFFSET: usize = 478;
+
+/// The offset of the color correction table, postage stamp, and scan line table offsets in the extension area.
+const TABLE_OFFSETS: (usize, usize) = (482, 12);
+
+/// The offset of the attributes type in the extension area.
+const ATTRIBUTES_TYPE_OFFSET: usize = 494;
+
+/// The denominator used when storing a gamma value.
+const GAMMA_DENOMINATOR: u16 = 1000;
+
+/// The fields of a TGA 2.0 extension area.
+/// 
+/// Text fields are ASCII and stored null-terminated in fixed-size fields. Fields that are not listed here, such as the key color, are kept unchanged when the area is stored in an image. The color correction table, postage stamp, and scan line table are not kept, so their offsets are cleared when the image is written.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TgaExtensionArea {
+    /// The name of the image's author, up to 40 bytes.
+    pub author_name: String,
+    /// Comments from the author as up to 4 lines of up to 80 bytes, separated by `\n`.
+    pub author_comments: String,
+    /// The date and time the image was saved, as the month, day, year, hour, minute, and second.
+    pub date_time: [u16; 6],
+    /// The name or id of the job the image belongs to, up to 40 bytes.
+    pub job_name: String,
+    /// The time spent on the job, as hours, minutes, and seconds.
+    pub job_time: [u16; 3],
+    /// The name of the software that created the image, up to 40 bytes.
+    pub software_id: String,
+    /// The version of the software times 100, such as 213 for version 2.13.
+    pub software_version: u16,
+    /// The gamma value, or `None` if it is unused.
+    pub gamma: Option<f32>,
+    /// How the alpha channel should be interpreted: 0 for no alpha, 1 or 2 for undefined data that can be ignored or should be kept, 3 for alpha, and 4 for premultiplied alpha.
+    pub attributes_type: u8,
+}
+
+impl TgaImage {
+    /// Returns the fields of the image's extension area.
+    /// 
+    /// Returns `None` if the image has no extension area.
+    pub fn extension_area(&self) -> Option<TgaExtensionArea> {
+        let extension = self.extension.as_ref()?;
+
+        Some(TgaExtensionArea {
+            author_name: read_text(extension, AUTHOR_NAME),
+            author_comments: (0..COMMENT_LINES)
+                .map(|line| read_text(extension, (AUTHOR_COMMENTS_OFFSET + line * COMMENT_LINE_SIZE, COMMENT_LINE_SIZE)))
+                .collect::<Vec<_>>()
+                .join("\n")
+                .trim_end_matches('\n')
+                .to_string(),
+            date_time: read_u16s(extension, DATE_TIME_OFFSET),
+            job_name: read_text(extension, JOB_NAME),
+            job_time: read_u16s(extension, JOB_TIME_OFFSET),
+            software_id: read_text(extension, SOFTWARE_ID),
+            software_version: read_u16s::<1>(extension, SOFTWARE_VERSION_OFFSET)[0],
+            gamma: read_gamma(extension),
+            attributes_type: extension[ATTRIBUTES_TYPE_OFFSET]
+        })
+    }
+
+    /// Tries to store `area` in the image's extension area, creating an empty extension area if the image has none.
+    /// 
+    /// The extension area is written after the pixel data by `to_bytes`, followed by a TGA 2.0 footer. The gamma value is stored as described in `set_gamma`.
+    /// 
+    /// # Errors
+    /// If a text field is too long or not ASCII, or the comments have more than 4 lines, returns `InvalidArgument` error. The image is not changed.
+    pub fn set_extension_area(&mut self, area: &TgaExtensionArea) -> Result<(), TgaError> {
+        // Ensure the comments fit in the comment lines
+        let comments: Vec<&str> = if area.author_comments.is_empty() { vec![] } else { area.author_comments.split('\n').collect() };
+        if comments.len() > COMMENT_LINES {
+            return Err(InvalidArgument);
+        }
+
+        // Write the fields into a copy so the image is unchanged if any field is invalid
+        let mut extension = self.extension.clone().unwrap_or_else(new_extension_area);
+        write_text(&mut extension, AUTHOR_NAME, &area.author_name)?;
+        for line in 0..COMMENT_LINES {
+            let field = (AUTHOR_COMMENTS_OFFSET + line * COMMENT_LINE_SIZE, COMMENT_LINE_SIZE);
+            write_text(&mut extension, field, comments.get(line).copied().unwrap_or(""))?;
+        }
+        write_text(&mut extension, JOB_NAME, &area.job_name)?;
+        write_text(&mut extension, SOFTWARE_ID, &area.software_id)?;
+
+        write_u16s(&mut extension, DATE_TIME_OFFSET, &area.date_time);
+        write_u16s(&mut extension, JOB_TIME_OFFSET, &area.job_time);
+        write_u16s(&mut extension, SOFTWARE_VERSION_OFFSET, &[area.software_version]);
+        write_gamma(&mut extension, area.gamma);
+        extension[ATTRIBUTES_TYPE_OFFSET] = area.attributes_type;
+        self.extension = Some(extension);
+
+        Ok(())
+    }
+
+    /// Returns the gamma value stored in the extension area.
+    /// 
+    /// Returns `None` if the image has no extension area or the gamma value is unused.
+    pub fn gamma(&self) -> Option<f32> {
+        read_gamma(self.extension.as_ref()?)
+    }
+
+    /// Stores `gamma` in the extension area, creating an empty extension area if the image has none.
+    /// 
+    /// The gamma value is stored as a fraction of thousandths, so it is rounded to three decimal places and clamped to 0-65.535. The pixel data is not changed; use `apply_gamma` to gamma correct the pixels.
+    pub fn set_gamma(&mut self, gamma: f32) {
+        let extension = self.extension.get_or_insert_with(new_extension_area);
+        write_gamma(extension, Some(gamma));
+    }
+}
+
+/// Returns the gamma value in `extension`, or `None` if it is unused.
+fn read_gamma(extension: &[u8]) -> Option<f32> {
+    let [numerator, denominator] = read_u16s(extension, GAMMA_OFFSET);
+    if denominator == 0 {
+        return None;
+    }
+
+    Some(numerator as f32 / denominator as f32)
+}
+
+/// Writes `gamma` to `extension` as a fraction of thousandths, or marks it unused if it is `None`.
+fn write_gamma(extension: &mut [u8], gamma: Option<f32>) {
+    let fraction = match gamma {
+        Some(gamma) => [(gamma * GAMMA_DENOMINATOR as f32).round().clamp(0.0, u16::MAX as f32) as u16, GAMMA_DENOMINATOR],
+        None => [0, 0]
+    };
+    write_u16s(extension, GAMMA_OFFSET, &fraction);
+}
+
+/// Returns the null-terminated text in the `(offset, size)` field of `extension`.
+fn read_text(extension: &[u8], field: (usize, usize)) -> String {
+    let (offset, size) = field;
+    let bytes = &extension[offset..offset + size];
+    let len = bytes.iter().position(|&b| b == 0).unwrap_or(size);
+    String::from_utf8_lossy(&bytes[..len]).into_owned()
+}
+
+/// Tries to write `text` into the `(offset, size)` field of `extension`, padding it with zeros.
+/// 
+/// # Errors
+/// If `text` is not ASCII or does not fit in the field with a null terminator, returns `InvalidArgument` error.
+fn write_text(extension: &mut [u8], field: (usize, usize), text: &str) -> Result<(), TgaError> {
+    let (offset, size) = field;
+    if !text.is_ascii() || text.len() >= size {
+        return Err(InvalidArgument);
+    }
+
+    let bytes = &mut extension[offset..offset + size];
+    bytes.fill(0);
+    bytes[..text.len()].copy_from_slice(text.as_bytes());
+    Ok(())
+}
+
+/// Returns `N` consecutive little-endian `u16` values starting at `offset` in `extension`.
+fn read_u16s<const N: usize>(extension: &[u8], offset: usize) -> [u16; N] {
+    let mut values = [0; N];
+    for (i, value) in values.iter_mut().enumerate() {
+        let start = offset + i * 2;
+        *value = u16::from_le_bytes([extension[start], extension[start + 1]]);
+    }
+    values
+}
+
+/// Writes `values` as consecutive little-endian `u16` values starting at `offset` in `extension`.
+fn write_u16s(extension: &mut [u8], offset: usize, values: &[u16]) {
+    for (i, value) in values.iter().enumerate() {
+        let start = offset + i * 2;
+        extension[start..start + 2].copy_from_slice(&value.to_le_bytes());
+    }
+}
+
+/// Returns an extension area with every field unused.
+fn new_extension_area() -> Box<[u8]> {
+    let mut extension = vec![0; EXTENSION_SIZE];
+    extension[0..2].copy_from_slice(&(EXTENSION_SIZE as u16).to_le_bytes());
+    extension.into_boxed_slice()
+}
+
+/// Appends `extension` to `buf` with its color correction table, postage stamp, and scan line table offsets cleared, and returns its offset.
+/// 
+/// The offsets are cleared because those tables are not written, so the offsets read from the original file would no longer point to them.
+pub(crate) fn write_extension_area(buf: &mut Vec<u8>, extension: &[u8]) -> u32 {
+    let offset = buf.len() as u32;
+    buf.extend_from_slice(extension);
+    let (start, size) = TABLE_OFFSETS;
+    let start = offset as usize + start;
+    buf[start..start + size].fill(0);
+    offset
+}
+
+/// Tries to read the extension area at `offset` in `buf`.
+/// 
+/// # Errors
+/// If the extension area does not fit in `buf`, or its size is smaller than a TGA 2.0 extension area, returns `InvalidSize` error.
+pub(crate) fn parse_extension_area(buf: &[u8], offset: usize) -> Result<Box<[u8]>, TgaError> {
+    let extension = buf.get(offset..offset + EXTENSION_SIZE).ok_or(InvalidSize)?;
+    let size = u16::from_le_bytes(extension[0..2].try_into().map_err(|_| {InvalidSize})?) as usize;
+    if size < EXTENSION_SIZE {
+        return Err(InvalidSize);
+    }
+
+    Ok(extension.into())
+}
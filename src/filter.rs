@@ -0,0 +1,224 @@
+use crate::{TgaError, TgaImage};
+use crate::TgaError::*;
+
+impl TgaImage {
+    /// Tries to multiply each color channel by the pixel's normalized alpha.
+    /// 
+    /// # Errors
+    /// If the image is not a 32-bit true color image, returns `InvalidImageType` error.
+    pub fn premultiply_alpha(&mut self) -> Result<(), TgaError> {
+        self.check_rgba()?;
+
+        for pixel in self.data.chunks_exact_mut(4) {
+            let alpha = pixel[3] as u32;
+            for channel in &mut pixel[0..3] {
+                *channel = ((*channel as u32 * alpha + 127) / 255) as u8;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Tries to divide each color channel by the pixel's normalized alpha.
+    /// 
+    /// This reverses `premultiply_alpha`, up to rounding error. Fully transparent pixels become black.
+    /// 
+    /// # Errors
+    /// If the image is not a 32-bit true color image, returns `InvalidImageType` error.
+    pub fn unpremultiply_alpha(&mut self) -> Result<(), TgaError> {
+        self.check_rgba()?;
+
+        for pixel in self.data.chunks_exact_mut(4) {
+            let alpha = pixel[3] as u32;
+            for channel in &mut pixel[0..3] {
+                *channel = (*channel as u32 * 255 + alpha / 2).checked_div(alpha).unwrap_or(0).min(255) as u8;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Tries to convolve each color channel with a 3x3 `kernel`.
+    /// 
+    /// Each weighted sum is divided by `divisor`, offset by `bias`, and clamped to 0-255. `kernel[1][1]` is the weight of the pixel itself, and pixels outside of the image are clamped to the nearest edge pixel. The alpha channel is left unchanged.
+    /// 
+    /// # Errors
+    /// If the image is not an 8-bit greyscale or 24/32-bit true color image, returns `InvalidImageType` error.
+    /// 
+    /// If `divisor` is zero, returns `InvalidArgument` error.
+    pub fn apply_kernel(&mut self, kernel: [[f32; 3]; 3], divisor: f32, bias: f32) -> Result<(), TgaError> {
+        let channels = self.color_channels()?;
+        if divisor == 0.0 {
+            return Err(InvalidArgument);
+        }
+
+        // Convolve into a new buffer so that every pixel reads the original values
+        let width = self.header.width as i32;
+        let height = self.header.height as i32;
+        let mut data = self.data.clone();
+        for y in 0..height {
+            for x in 0..width {
+                let dest = self.pixel_offset(x as u16, y as u16);
+                for channel in 0..channels {
+                    let mut sum = 0.0;
+                    for (ky, row) in kernel.iter().enumerate() {
+                        for (kx, weight) in row.iter().enumerate() {
+                            let sx = (x + kx as i32 - 1).clamp(0, width - 1);
+                            let sy = (y + ky as i32 - 1).clamp(0, height - 1);
+                            let src = self.pixel_offset(sx as u16, sy as u16);
+                            sum += weight * self.data[src + channel] as f32;
+                        }
+                    }
+                    data[dest + channel] = (sum / divisor + bias).round().clamp(0.0, 255.0) as u8;
+                }
+            }
+        }
+        self.data = data;
+
+        Ok(())
+    }
+
+    /// Tries to apply gamma correction to each color channel.
+    /// 
+    /// Each normalized channel value is raised to the power `1.0 / gamma`, so a `gamma` greater than 1 brightens midtones. The alpha channel is left unchanged. Only the pixel data is changed; the gamma value stored in the extension area by `set_gamma` is not updated.
+    /// 
+    /// # Errors
+    /// If the image is not an 8-bit greyscale or 24/32-bit true color image, returns `InvalidImageType` error.
+    /// 
+    /// If `gamma` is not a positive number, returns `InvalidArgument` error.
+    pub fn apply_gamma(&mut self, gamma: f32) -> Result<(), TgaError> {
+        if gamma.is_nan() || gamma <= 0.0 {
+            return Err(InvalidArgument);
+        }
+
+        let mut lut = [0; 256];
+        for (value, entry) in lut.iter_mut().enumerate() {
+            *entry = ((value as f32 / 255.0).powf(1.0 / gamma) * 255.0).round().clamp(0.0, 255.0) as u8;
+        }
+
+        self.map_color_channels(&lut)
+    }
+
+    /// Tries to replace each color channel value with its entry in `lut`.
+    /// 
+    /// This applies any precomputed tone curve, such as gamma, levels, or inversion. The alpha channel is left unchanged.
+    /// 
+    /// # Errors
+    /// If the image is not an 8-bit greyscale or 24/32-bit true color image, returns `InvalidImageType` error.
+    pub fn apply_lut(&mut self, lut: &[u8; 256]) -> Result<(), TgaError> {
+        self.map_color_channels(lut)
+    }
+
+    /// Tries to invert each color channel, leaving the alpha channel unchanged.
+    /// 
+    /// # Errors
+    /// If the image is not an 8-bit greyscale or 24/32-bit true color image, returns `InvalidImageType` error.
+    pub fn invert(&mut self) -> Result<(), TgaError> {
+        let channels = self.color_channels()?;
+        let byte_depth = self.byte_depth();
+
+        for pixel in self.data.chunks_exact_mut(byte_depth) {
+            for channel in &mut pixel[0..channels] {
+                *channel = !*channel;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Tries to scale the saturation of each pixel by `factor`.
+    /// 
+    /// Each pixel is converted to HSL, its saturation is multiplied by `factor` and clamped to 0-1, and it is converted back. A `factor` of 0 makes the image grey, and a `factor` greater than 1 makes colors more vivid. The alpha channel is left unchanged.
+    /// 
+    /// # Errors
+    /// If the image is not a 24/32-bit true color image, returns `InvalidImageType` error.
+    /// 
+    /// If `factor` is negative or not a number, returns `InvalidArgument` error.
+    pub fn adjust_saturation(&mut self, factor: f32) -> Result<(), TgaError> {
+        if !self.header.image_type.is_true_color() {
+            return Err(InvalidImageType);
+        }
+        self.color_channels()?;
+        if factor.is_nan() || factor < 0.0 {
+            return Err(InvalidArgument);
+        }
+
+        let byte_depth = self.byte_depth();
+        for pixel in self.data.chunks_exact_mut(byte_depth) {
+            let (hue, saturation, lightness) = rgb_to_hsl(pixel[2], pixel[1], pixel[0]);
+            let (r, g, b) = hsl_to_rgb(hue, (saturation * factor).clamp(0.0, 1.0), lightness);
+            pixel[0] = b;
+            pixel[1] = g;
+            pixel[2] = r;
+        }
+
+        Ok(())
+    }
+
+    /// Tries to replace each color channel value with its entry in `lut`, leaving the alpha channel unchanged.
+    fn map_color_channels(&mut self, lut: &[u8; 256]) -> Result<(), TgaError> {
+        let channels = self.color_channels()?;
+        let byte_depth = self.byte_depth();
+
+        for pixel in self.data.chunks_exact_mut(byte_depth) {
+            for channel in &mut pixel[0..channels] {
+                *channel = lut[*channel as usize];
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Ensures this image is a 32-bit true color image.
+    pub(crate) fn check_rgba(&self) -> Result<(), TgaError> {
+        if !self.header.image_type.is_true_color() || self.header.image_bit_depth != 32 {
+            return Err(InvalidImageType);
+        }
+
+        Ok(())
+    }
+}
+
+/// Converts an RGB color to hue (0-1), saturation (0-1), and lightness (0-1).
+fn rgb_to_hsl(r: u8, g: u8, b: u8) -> (f32, f32, f32) {
+    let r = r as f32 / 255.0;
+    let g = g as f32 / 255.0;
+    let b = b as f32 / 255.0;
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let lightness = (max + min) / 2.0;
+    let delta = max - min;
+    if delta == 0.0 {
+        return (0.0, 0.0, lightness);
+    }
+
+    let saturation = delta / (1.0 - (2.0 * lightness - 1.0).abs());
+    let hue = if max == r {
+        ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        (b - r) / delta + 2.0
+    } else {
+        (r - g) / delta + 4.0
+    };
+
+    (hue / 6.0, saturation.min(1.0), lightness)
+}
+
+/// Converts a hue (0-1), saturation (0-1), and lightness (0-1) to an RGB color.
+fn hsl_to_rgb(hue: f32, saturation: f32, lightness: f32) -> (u8, u8, u8) {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let sector = hue * 6.0;
+    let second = chroma * (1.0 - (sector.rem_euclid(2.0) - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, second, 0.0),
+        1 => (second, chroma, 0.0),
+        2 => (0.0, chroma, second),
+        3 => (0.0, second, chroma),
+        4 => (second, 0.0, chroma),
+        _ => (chroma, 0.0, second)
+    };
+    let offset = lightness - chroma / 2.0;
+    let to_u8 = |value: f32| {((value + offset) * 255.0).round().clamp(0.0, 255.0) as u8};
+
+    (to_u8(r), to_u8(g), to_u8(b))
+}
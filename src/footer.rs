@@ -0,0 +1,25 @@
+use std::convert::TryInto;
+
+/// The size of a TGA 2.0 footer in bytes.
+pub(crate) const FOOTER_SIZE: usize = 26;
+
+/// The signature that ends a TGA 2.0 footer.
+pub(crate) const SIGNATURE: &[u8; 18] = b"TRUEVISION-XFILE.\0";
+
+/// Returns the extension area and developer area offsets from the TGA 2.0 footer at the end of `buf`.
+/// 
+/// Returns `None` if `buf` does not end with a footer.
+pub(crate) fn parse_footer(buf: &[u8]) -> Option<(u32, u32)> {
+    if buf.len() < FOOTER_SIZE {
+        return None;
+    }
+
+    let footer = &buf[buf.len() - FOOTER_SIZE..];
+    if &footer[8..] != SIGNATURE {
+        return None;
+    }
+
+    let extension_offset = u32::from_le_bytes(footer[0..4].try_into().ok()?);
+    let developer_offset = u32::from_le_bytes(footer[4..8].try_into().ok()?);
+    Some((extension_offset, developer_offset))
+}
@@ -0,0 +1,32 @@
+use crate::TgaImage;
+
+/// The layout of an image's pixels, derived from its image type and bit depth.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PixelFormat {
+    /// The number of bytes used by each pixel.
+    pub byte_depth: u8,
+    /// The number of channels in each pixel, as returned by `TgaImageType::channels`.
+    pub channels: u8,
+    /// Whether pixels have an alpha channel or attribute bit that is used as alpha.
+    pub has_alpha: bool,
+    /// Whether pixels are color map indices.
+    pub is_indexed: bool,
+}
+
+impl TgaImage {
+    /// Returns the layout of the image's pixels.
+    /// 
+    /// 32-bit true color pixels always have alpha, while the attribute bit of 16-bit pixels and the alpha byte of 16-bit greyscale pixels are only used as alpha if the descriptor declares alpha bits.
+    pub fn pixel_format(&self) -> PixelFormat {
+        let image_type = self.header.image_type;
+        let bit_depth = self.header.image_bit_depth;
+        let channels = image_type.channels(bit_depth);
+
+        PixelFormat {
+            byte_depth: self.byte_depth() as u8,
+            channels,
+            has_alpha: channels == 4 || (!image_type.is_color_mapped() && bit_depth == 16 && self.header.alpha_bits() > 0),
+            is_indexed: image_type.is_color_mapped()
+        }
+    }
+}
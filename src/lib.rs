@@ -2,14 +2,42 @@
 //!
 //! `rtga-rust` is a toy library for interfacing with TGA images.
 
+mod color_map;
+mod convert;
+mod descriptor;
+mod developer;
+mod diff;
+mod draw;
+mod extension;
+mod filter;
+mod footer;
+mod format;
+mod options;
+mod ppm;
+mod rle;
+mod stream;
 #[cfg(test)]
 mod tests;
+mod transform;
 
+use std::collections::HashSet;
 use std::convert::TryInto;
 use std::fs::File;
 use std::io::Error as IOError;
-use std::io::{Read, Write};
-use std::path::Path;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+pub use descriptor::TgaDescriptor;
+pub use developer::DeveloperTag;
+pub use diff::ImageDiff;
+pub use extension::TgaExtensionArea;
+pub use format::PixelFormat;
+pub use options::{DecodeOptions, Strictness};
+pub use rle::decode_rows;
+pub use stream::{TgaScanlineDecoder, TgaScanlineEncoder, TgaStreamWriter};
+pub use transform::ResizeFilter;
 
 use TgaColor::*;
 use TgaError::*;
@@ -18,28 +46,123 @@ use TgaImageType::*;
 /// The size of a TGA header in bytes.
 pub const HEADER_SIZE: usize = 18;
 
+/// The number of temporary files created by `to_file_atomic`, used to give each one a unique name.
+static TEMP_FILE_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// The descriptor bit set when pixels are stored from right to left.
+const DESCRIPTOR_RIGHT: u8 = 0x10;
+
+/// The descriptor bit set when rows are stored from top to bottom.
+const DESCRIPTOR_TOP: u8 = 0x20;
+
+/// The bits reserved in a TGA 2.0 descriptor, which must be zero.
+const DESCRIPTOR_RESERVED: u8 = 0xc0;
+
 /// The color formats used in a TGA image.
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum TgaColor {
     Greyscale([u8; 1]),
+    GreyscaleAlpha([u8; 2]),
     RGB16([u8; 2]),
     RGB24([u8; 3]),
     RGBA([u8; 4])
 }
 
 impl TgaColor {
+    /// Opaque black in the `RGB24` format.
+    pub const BLACK_RGB24: TgaColor = TgaColor::rgb24(0, 0, 0);
+
+    /// Opaque white in the `RGB24` format.
+    pub const WHITE_RGB24: TgaColor = TgaColor::rgb24(255, 255, 255);
+
+    /// Opaque black in the `RGBA` format.
+    pub const BLACK_RGBA: TgaColor = TgaColor::rgba(0, 0, 0, 255);
+
+    /// Opaque white in the `RGBA` format.
+    pub const WHITE_RGBA: TgaColor = TgaColor::rgba(255, 255, 255, 255);
+
+    /// Fully transparent black in the `RGBA` format.
+    pub const TRANSPARENT_RGBA: TgaColor = TgaColor::rgba(0, 0, 0, 0);
+
+    /// Creates an `RGB24` color from channels in RGB order.
+    /// 
+    /// The channels are stored in TGA's BGR order.
+    pub const fn rgb24(r: u8, g: u8, b: u8) -> TgaColor {
+        RGB24([b, g, r])
+    }
+
+    /// Creates an `RGBA` color from channels in RGBA order.
+    /// 
+    /// The channels are stored in TGA's BGRA order.
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> TgaColor {
+        RGBA([b, g, r, a])
+    }
+
+    /// Tries to create a color from a slice of color data.
+    /// 
+    /// The color format is determined by the length of the slice, so 2 bytes are always read as `RGB16`.
+    /// 
+    /// # Errors
+    /// If the length of `bytes` is not 1, 2, 3, or 4, returns `InvalidColor` error.
+    pub fn from_slice(bytes: &[u8]) -> Result<TgaColor, TgaError> {
+        match *bytes {
+            [v] => Ok(Greyscale([v])),
+            [b, g] => Ok(RGB16([b, g])),
+            [b, g, r] => Ok(RGB24([b, g, r])),
+            [b, g, r, a] => Ok(RGBA([b, g, r, a])),
+            _ => Err(InvalidColor)
+        }
+    }
+
     /// Extracts a slice containing the color data.
     /// 
     /// The length of the slice will be the same as the color's byte depth.
     pub fn as_slice(&self) -> &[u8] {
         match self {
             Greyscale(s) => &s[..],
+            GreyscaleAlpha(s) => &s[..],
             RGB16(s) => &s[..],
             RGB24(s) => &s[..],
             RGBA(s) => &s[..],
         }
     }
 
+    /// Returns the color as 8-bit channels in RGBA order.
+    /// 
+    /// Greyscale colors are replicated to every color channel, and `RGB16` channels are expanded by replicating their top bits. The alpha of `RGB16` colors is taken from the attribute bit, and other colors without alpha are opaque.
+    pub fn to_rgba8(&self) -> [u8; 4] {
+        match *self {
+            Greyscale([v]) => [v, v, v, 255],
+            GreyscaleAlpha([v, a]) => [v, v, v, a],
+            RGB16(bytes) => {
+                let [b, g, r] = convert::unpack_rgb16(bytes);
+                [r, g, b, if bytes[1] & 0x80 != 0 { 255 } else { 0 }]
+            }
+            RGB24([b, g, r]) => [r, g, b, 255],
+            RGBA([b, g, r, a]) => [r, g, b, a]
+        }
+    }
+
+    /// Returns the color as a `Greyscale` color.
+    /// 
+    /// Colors are converted to their luminance using the Rec. 601 weights, and alpha is dropped.
+    pub fn to_greyscale(&self) -> TgaColor {
+        let [r, g, b, _] = self.to_rgba8().map(|c| c as u32);
+        match self {
+            Greyscale(_) => *self,
+            GreyscaleAlpha([v, _]) => Greyscale([*v]),
+            _ => Greyscale([((299 * r + 587 * g + 114 * b + 500) / 1000) as u8])
+        }
+    }
+
+    /// Returns the color as an `RGB24` color.
+    /// 
+    /// Greyscale colors are replicated to every channel, `RGB16` channels are expanded to 8 bits, and alpha is dropped.
+    pub fn to_rgb24(&self) -> TgaColor {
+        let [r, g, b, _] = self.to_rgba8();
+        TgaColor::rgb24(r, g, b)
+    }
+
     /// Returns the color's full bit depth.
     pub fn bit_depth(&self) -> u8 {
         self.byte_depth() * 8
@@ -49,6 +172,7 @@ impl TgaColor {
     pub fn byte_depth(&self) -> u8 {
         match self {
             Greyscale(_) => 1,
+            GreyscaleAlpha(_) => 2,
             RGB16(_) => 2,
             RGB24(_) => 3,
             RGBA(_) => 4
@@ -59,17 +183,21 @@ impl TgaColor {
 /// An interface for editing a TGA image file.
 /// 
 /// Image data is saved in memory when editing and can be read from or written to a file. Provides functions for editing individual pixels. 
-#[derive(Clone)]
+/// 
+/// Pixel coordinates start at (0, 0) in the top-left corner of the image as displayed. The origin bits of the header's descriptor determine how pixels are ordered in the stored pixel data.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct TgaImage {
     pub header: TgaHeader,
     state: TgaImageState,
     id: Box<[u8]>,
     color_map: Box<[u8]>,
     data: Box<[u8]>,
+    developer_tags: Vec<DeveloperTag>,
+    extension: Option<Box<[u8]>>,
 }
 
 /// The possible types of a TGA image.
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum TgaImageType {
     NoImage = 0,
     ColorMappedImage = 1,
@@ -98,48 +226,123 @@ impl TgaImageType {
         }
     }
 
+    /// Returns true if the image type is run-length encoded.
+    pub fn is_rle(&self) -> bool {
+        matches!(self, RleColorMappedImage | RleTrueColorImage | RleBlackAndWhiteImage)
+    }
+
+    /// Returns true if the image type is color-mapped, with or without run-length encoding.
+    pub fn is_color_mapped(&self) -> bool {
+        matches!(self, ColorMappedImage | RleColorMappedImage)
+    }
+
+    /// Returns true if the image type is true color, with or without run-length encoding.
+    pub fn is_true_color(&self) -> bool {
+        matches!(self, TrueColorImage | RleTrueColorImage)
+    }
+
+    /// Returns true if the image type is greyscale, with or without run-length encoding.
+    pub fn is_greyscale(&self) -> bool {
+        matches!(self, BlackAndWhiteImage | RleBlackAndWhiteImage)
+    }
+
+    /// Returns the image type without run-length encoding.
+    pub fn base_type(&self) -> TgaImageType {
+        match self {
+            RleColorMappedImage => ColorMappedImage,
+            RleTrueColorImage => TrueColorImage,
+            RleBlackAndWhiteImage => BlackAndWhiteImage,
+            _ => *self
+        }
+    }
+
+    /// Returns the image type with run-length encoding.
+    /// 
+    /// `NoImage` has no run-length encoded type, so it is returned unchanged.
+    pub fn rle_type(&self) -> TgaImageType {
+        match self {
+            ColorMappedImage => RleColorMappedImage,
+            TrueColorImage => RleTrueColorImage,
+            BlackAndWhiteImage => RleBlackAndWhiteImage,
+            _ => *self
+        }
+    }
+
+    /// Returns the number of channels in each pixel of this image type with `bit_depth` bits per pixel.
+    /// 
+    /// Greyscale pixels and color map indices have 1 channel, or 2 for 16-bit greyscale pixels with alpha. True color pixels have 3 channels, or 4 if they are 32-bit; 15/16-bit pixels count as 3 packed channels. Returns 0 if `bit_depth` is invalid for the image type.
+    pub fn channels(&self, bit_depth: u8) -> u8 {
+        if !self.valid_depth(bit_depth) {
+            return 0;
+        }
+
+        match self {
+            NoImage => 0,
+            TrueColorImage | RleTrueColorImage if bit_depth == 32 => 4,
+            TrueColorImage | RleTrueColorImage => 3,
+            BlackAndWhiteImage | RleBlackAndWhiteImage if bit_depth == 16 => 2,
+            ColorMappedImage | RleColorMappedImage |
+            BlackAndWhiteImage | RleBlackAndWhiteImage => 1
+        }
+    }
+
     /// Returns true if `color` is in a valid format for the image type.
+    /// 
+    /// The pixels of color-mapped images are color map indices, which use the `Greyscale` format for 8-bit indices and the `RGB16` format for little-endian 16-bit indices. 16-bit black and white images use the `GreyscaleAlpha` format.
     pub fn valid_color(&self, color: TgaColor) -> bool {
         match self {
             NoImage => false,
-            ColorMappedImage | TrueColorImage |
-            RleColorMappedImage |
-            RleTrueColorImage => match color {
-                Greyscale(_) => false,
-                _ => true
-            },
-            BlackAndWhiteImage | RleBlackAndWhiteImage => match color {
-                Greyscale(_) => true,
-                _ => false
-            }
+            TrueColorImage | RleTrueColorImage => !matches!(color, Greyscale(_) | GreyscaleAlpha(_)),
+            ColorMappedImage | RleColorMappedImage => matches!(color, Greyscale(_) | RGB16(_)),
+            BlackAndWhiteImage | RleBlackAndWhiteImage => matches!(color, Greyscale(_) | GreyscaleAlpha(_))
         }
     }
 
     /// Returns true if `bit_depth` is a valid bit depth for the image type.
+    /// 
+    /// A 15-bit true color pixel is stored like a 16-bit pixel without an attribute bit, and a 16-bit black and white pixel is an 8-bit grey value followed by an 8-bit alpha value. For color-mapped images, this is the bit depth of each color map index.
     pub fn valid_depth(&self, bit_depth: u8) -> bool {
         match self {
             NoImage => bit_depth == 0,
-            ColorMappedImage | TrueColorImage |
-            RleColorMappedImage |
-            RleTrueColorImage => match bit_depth {
-                16 | 24 | 32 => true,
-                _ => false
-            }
-            BlackAndWhiteImage | RleBlackAndWhiteImage => bit_depth == 8
+            TrueColorImage | RleTrueColorImage => matches!(bit_depth, 15 | 16 | 24 | 32),
+            ColorMappedImage | RleColorMappedImage => matches!(bit_depth, 8 | 16),
+            BlackAndWhiteImage | RleBlackAndWhiteImage => matches!(bit_depth, 8 | 16)
         }
     }
 }
 
 /// The current state of a TGA image in memory.
-#[derive(Copy, Clone)]
+/// 
+/// Run-length encoded pixel data is decoded when an image is read, so images in memory are `Uncompressed`, or `ColorMapped` if their pixels are color map indices.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum TgaImageState {
     Uncompressed,
     ColorMapped,
     Rle,
 }
 
+impl TgaImageState {
+    /// Returns the state of decoded pixel data of `image_type`.
+    fn decoded(image_type: TgaImageType) -> TgaImageState {
+        if image_type.is_color_mapped() {
+            TgaImageState::ColorMapped
+        } else {
+            TgaImageState::Uncompressed
+        }
+    }
+}
+
+/// The versions of the TGA file format.
+/// 
+/// A file is version 2.0 if it ends with a valid TGA 2.0 footer, and version 1.0 otherwise.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TgaVersion {
+    V1,
+    V2,
+}
+
 /// The header for a TGA image file.
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct TgaHeader {
     pub id_size: u8,
     pub has_color_map: bool,
@@ -156,65 +359,169 @@ pub struct TgaHeader {
 }
 
 impl TgaHeader {
+    /// Tries to read a `TgaHeader` from the first `HEADER_SIZE` bytes of `reader`.
+    /// 
+    /// Nothing past the header is read, so the reader is left at the start of the image id.
+    /// 
+    /// # Errors
+    /// If the data could not be read, returns `FileRead` error.
+    /// 
+    /// If the data is shorter than a TGA header, returns `InvalidSize` error.
+    /// 
+    /// If the header is invalid, returns the same errors as `from_buf`.
+    pub fn from_reader<R: Read>(reader: &mut R) -> Result<TgaHeader, TgaError> {
+        let mut header_buf = [0; HEADER_SIZE];
+        rle::read_exact(reader, &mut header_buf)?;
+
+        TgaHeader::from_buf(header_buf)
+    }
+
+    /// Tries to read the `TgaHeader` of a TGA image file.
+    /// 
+    /// Only the first `HEADER_SIZE` bytes of the file are read, so this is much cheaper than loading the image when only its dimensions or format are needed. The rest of the file is not validated.
+    /// 
+    /// # Errors
+    /// If the file could not be opened, returns `FileOpen` error.
+    /// 
+    /// Otherwise, returns the same errors as `from_reader`.
+    pub fn from_file<P: AsRef<Path>>(filename: P) -> Result<TgaHeader, TgaError> {
+        let filename = filename.as_ref();
+        let mut file = open_file(filename)?;
+
+        TgaHeader::from_reader(&mut file).map_err(|e| {e.with_path(filename)})
+    }
+
     /// Tries to create a `TgaHeader` from the data in `buf`.
     /// 
     /// # Errors
-    /// TODO: Change expect() calls to `TgaError`s
+    /// If the image type is unknown, returns `InvalidImageType` error.
+    /// 
+    /// If either of the descriptor's reserved bits 6-7 is set, returns `InvalidArgument` error.
     pub fn from_buf(buf: [u8; HEADER_SIZE]) -> Result<TgaHeader, TgaError> {
         Ok(TgaHeader {
             id_size: buf[0],
             has_color_map: buf[1] != 0,
             image_type: TgaImageType::from_u8(buf[2])?,
-            color_map_first_index: u16::from_le_bytes(buf[3..5].try_into().expect("bad slice")),
-            color_map_size: u16::from_le_bytes(buf[5..7].try_into().expect("bad slice")),
+            color_map_first_index: u16::from_le_bytes([buf[3], buf[4]]),
+            color_map_size: u16::from_le_bytes([buf[5], buf[6]]),
             color_map_bit_depth: buf[7],
-            x_origin: u16::from_le_bytes(buf[8..10].try_into().expect("bad slice")),
-            y_origin: u16::from_le_bytes(buf[10..12].try_into().expect("bad slice")),
-            width: u16::from_le_bytes(buf[12..14].try_into().expect("bad slice")),
-            height: u16::from_le_bytes(buf[14..16].try_into().expect("bad slice")),
+            x_origin: u16::from_le_bytes([buf[8], buf[9]]),
+            y_origin: u16::from_le_bytes([buf[10], buf[11]]),
+            width: u16::from_le_bytes([buf[12], buf[13]]),
+            height: u16::from_le_bytes([buf[14], buf[15]]),
             image_bit_depth: buf[16],
-            descriptor: buf[17]
+            descriptor: TgaDescriptor::from_u8(buf[17])?.to_u8()
         })
     }
 
+    /// Returns the number of attribute (alpha) bits per pixel.
+    /// 
+    /// This is stored in bits 0-3 of the descriptor.
+    pub fn alpha_bits(&self) -> u8 {
+        self.descriptor & 0x0f
+    }
+
+    /// Tries to set the number of attribute (alpha) bits per pixel.
+    /// 
+    /// Only bits 0-3 of the descriptor are changed.
+    /// 
+    /// # Errors
+    /// If `bits` is greater than 8, returns `InvalidPixelDepth` error.
+    /// 
+    /// If `bits` does not fit the image's bit depth (at most 8 for 16-bit greyscale, 1 for other 16-bit, 8 for 32-bit, and 0 otherwise), returns `InvalidPixelDepth` error.
+    pub fn set_alpha_bits(&mut self, bits: u8) -> Result<(), TgaError> {
+        let max_bits = match self.image_bit_depth {
+            16 if self.image_type.is_greyscale() => 8,
+            16 => 1,
+            32 => 8,
+            _ => 0
+        };
+        if bits > max_bits {
+            return Err(InvalidPixelDepth);
+        }
+
+        self.descriptor = (self.descriptor & 0xf0) | bits;
+
+        Ok(())
+    }
+
     /// Returns the size of the TGA image in bytes.
     /// 
     /// Includes the header, color map, id, and pixel data.
     pub fn file_size(&self) -> usize {
-        HEADER_SIZE as usize + self.id_size as usize + self.color_map_size as usize + self.image_size()
+        (HEADER_SIZE + self.id_size as usize + self.color_map_byte_size()).saturating_add(self.image_size())
+    }
+
+    /// Returns the size of the color map in bytes.
+    /// 
+    /// `color_map_size` is the number of entries in the color map, and each entry uses `color_map_bit_depth` bits rounded up to whole bytes.
+    pub fn color_map_byte_size(&self) -> usize {
+        self.color_map_size as usize * (self.color_map_bit_depth as usize).div_ceil(8)
     }
 
     /// Returns the size of the TGA image pixel data in bytes.
+    /// 
+    /// If the size does not fit in a `usize`, returns `usize::MAX`.
     pub fn image_size(&self) -> usize {
-        image_size(self.width, self.height, self.image_bit_depth)
+        image_size(self.width, self.height, self.image_bit_depth).unwrap_or(usize::MAX)
+    }
+
+    /// Ensures the pixel depth is valid for the image type.
+    fn check_pixel_depth(&self) -> Result<(), TgaError> {
+        if self.image_type.is_color_mapped() && !self.image_type.valid_depth(self.image_bit_depth) {
+            return Err(UnsupportedIndexSize);
+        }
+        if !self.image_type.valid_depth(self.image_bit_depth) {
+            return Err(InvalidPixelDepth);
+        }
+
+        Ok(())
     }
 
     /// Returns the header as a byte array.
+    /// 
+    /// Multi-byte fields are written in little-endian order, matching `from_buf`.
     pub fn to_buf(&self) -> [u8; HEADER_SIZE] {
-        [
-            self.id_size,
-            if self.has_color_map { 1 } else { 0 },
-            self.image_type as u8,
-            self.color_map_first_index as u8 & 0xff,
-            (self.color_map_first_index >> 8) as u8,
-            self.color_map_size as u8 & 0xff,
-            (self.color_map_size >> 8) as u8,
-            self.color_map_bit_depth,
-            self.x_origin as u8 & 0xff,
-            (self.x_origin >> 8) as u8,
-            self.y_origin as u8 & 0xff,
-            (self.y_origin >> 8) as u8,
-            self.width as u8 & 0xff,
-            (self.width >> 8) as u8,
-            self.height as u8 & 0xff,
-            (self.height >> 8) as u8,
-            self.image_bit_depth,
-            self.descriptor
-        ]
+        let mut buf = [0; HEADER_SIZE];
+        buf[0] = self.id_size;
+        buf[1] = self.has_color_map as u8;
+        buf[2] = self.image_type as u8;
+        buf[3..5].copy_from_slice(&self.color_map_first_index.to_le_bytes());
+        buf[5..7].copy_from_slice(&self.color_map_size.to_le_bytes());
+        buf[7] = self.color_map_bit_depth;
+        buf[8..10].copy_from_slice(&self.x_origin.to_le_bytes());
+        buf[10..12].copy_from_slice(&self.y_origin.to_le_bytes());
+        buf[12..14].copy_from_slice(&self.width.to_le_bytes());
+        buf[14..16].copy_from_slice(&self.height.to_le_bytes());
+        buf[16] = self.image_bit_depth;
+        buf[17] = self.descriptor;
+        buf
+    }
+}
+
+impl Default for TgaHeader {
+    /// Returns the header of a 1x1 24-bit `TrueColorImage` with no id or color map.
+    fn default() -> TgaHeader {
+        TgaHeader {
+            id_size: 0,
+            has_color_map: false,
+            image_type: TrueColorImage,
+            color_map_first_index: 0,
+            color_map_size: 0,
+            color_map_bit_depth: 0,
+            x_origin: 0,
+            y_origin: 0,
+            width: 1,
+            height: 1,
+            image_bit_depth: 24,
+            descriptor: 0
+        }
     }
 }
 
 /// An error resulting from one of this library's functions.
+/// 
+/// The IO errors carry the path of the file being accessed when they come from a file-based function such as `from_file` or `to_file`, and `None` when they come from a reader or writer.
 #[derive(Debug)]
 pub enum TgaError {
     InvalidPixelDepth,
@@ -222,24 +529,91 @@ pub enum TgaError {
     InvalidSize,
     InvalidCoordinate,
     InvalidColor,
-    FileOpen(IOError),
-    FileRead(IOError),
-    FileWrite(IOError),
+    InvalidArgument,
+    InvalidColorMap,
+    UnsupportedIndexSize,
+    InvalidFrame(usize, Box<TgaError>),
+    AllocationFailed,
+    FileOpen(IOError, Option<PathBuf>),
+    FileRead(IOError, Option<PathBuf>),
+    FileWrite(IOError, Option<PathBuf>),
+}
+
+impl TgaError {
+    /// Returns the error with `path` attached if it is an IO error without a path.
+    fn with_path(self, path: &Path) -> TgaError {
+        match self {
+            FileOpen(e, None) => FileOpen(e, Some(path.to_path_buf())),
+            FileRead(e, None) => FileRead(e, Some(path.to_path_buf())),
+            FileWrite(e, None) => FileWrite(e, Some(path.to_path_buf())),
+            e => e
+        }
+    }
+}
+
+impl fmt::Display for TgaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidPixelDepth => write!(f, "invalid pixel depth"),
+            InvalidImageType => write!(f, "invalid image type"),
+            InvalidSize => write!(f, "invalid size"),
+            InvalidCoordinate => write!(f, "invalid coordinate"),
+            InvalidColor => write!(f, "invalid color"),
+            InvalidArgument => write!(f, "invalid argument"),
+            InvalidColorMap => write!(f, "invalid color map"),
+            UnsupportedIndexSize => write!(f, "unsupported color map index size"),
+            InvalidFrame(index, e) => write!(f, "invalid frame {}: {}", index, e),
+            AllocationFailed => write!(f, "allocation failed"),
+            FileOpen(e, path) => write_io_error(f, "open", e, path),
+            FileRead(e, path) => write_io_error(f, "read", e, path),
+            FileWrite(e, path) => write_io_error(f, "write", e, path)
+        }
+    }
+}
+
+/// Writes an IO error message, including the path if there is one.
+fn write_io_error(f: &mut fmt::Formatter<'_>, action: &str, e: &IOError, path: &Option<PathBuf>) -> fmt::Result {
+    match path {
+        Some(path) => write!(f, "failed to {} {}: {}", action, path.display(), e),
+        None => write!(f, "failed to {}: {}", action, e)
+    }
 }
 
 impl TgaImage {
-    /// Tries to create a new color with black pixels.
+    /// Tries to create a new image with black pixels.
+    /// 
+    /// Color-mapped images get a 256-entry 24-bit greyscale color map where each index is its own grey value, so every pixel starts as index 0, which is black. Use `new_color_mapped` to choose the palette.
     /// 
     /// # Errors
+    /// If `image_type` is color-mapped and `bit_depth` is not 8 or 16, returns `UnsupportedIndexSize` error.
+    /// 
     /// If `bit_depth` is invalid for `image_type`, returns `InvalidPixelDepth` error.
+    /// 
+    /// If `width` or `height` is zero, returns `InvalidSize` error.
+    /// 
+    /// If the pixel data is too large to allocate, returns `InvalidSize` error.
     pub fn new(image_type: TgaImageType, width: u16, height: u16, bit_depth: u8) -> Result<TgaImage, TgaError> {
         // Ensure the pixel depth is valid
+        if image_type.is_color_mapped() && !image_type.valid_depth(bit_depth) {
+            return Err(UnsupportedIndexSize);
+        }
         if !image_type.valid_depth(bit_depth) {
             return Err(InvalidPixelDepth);
         }
 
+        // Ensure the image has pixels and its size does not overflow
+        if width == 0 || height == 0 {
+            return Err(InvalidSize);
+        }
+        let size = image_size(width, height, bit_depth).ok_or(InvalidSize)?;
+
+        // Allocate pixel data without aborting on failure
+        let mut data = vec![];
+        data.try_reserve_exact(size).map_err(|_| {InvalidSize})?;
+        data.resize(size, 0);
+
         // Create header
-        let header = TgaHeader {
+        let mut header = TgaHeader {
             id_size: 0,
             has_color_map: false,
             image_type,
@@ -254,119 +628,962 @@ impl TgaImage {
             descriptor: 0
         };
 
+        // Ensure color-mapped images have a usable color map
+        let mut color_map = vec![];
+        if image_type.is_color_mapped() {
+            header.has_color_map = true;
+            header.color_map_size = 256;
+            header.color_map_bit_depth = 24;
+            color_map = (0..=255).flat_map(|v| [v, v, v]).collect();
+        }
+
         Ok(TgaImage {
             header,
-            state: TgaImageState::Uncompressed,
+            state: TgaImageState::decoded(image_type),
             id: vec![].into_boxed_slice(),
-            color_map: vec![].into_boxed_slice(),
-            data: vec![0; image_size(width, height, bit_depth)].into_boxed_slice()
+            color_map: color_map.into_boxed_slice(),
+            data: data.into_boxed_slice(),
+            developer_tags: vec![],
+            extension: None
         })
     }
 
-    /// Tries to read a TGA image from a file.
+    /// Tries to create a new image with every pixel set to `color`.
     /// 
     /// # Errors
-    /// If the file could not be opened, returns `FileOpen` error.
+    /// If `bit_depth` is invalid for `image_type`, returns `InvalidPixelDepth` error.
     /// 
-    /// If the file could not be read, returns `FileRead` error.
+    /// If `width` or `height` is zero, returns `InvalidSize` error.
+    /// 
+    /// If the pixel data is too large to allocate, returns `InvalidSize` error.
     /// 
-    /// If the file is not large enough to contain a TGA header, returns `InvalidSize` error.
+    /// If `color` is invalid for `image_type`, returns `InvalidColor` error.
     /// 
-    /// If the file is not large enough to contain the TGA image size read from the header, returns `InvalidSize` error.
+    /// If `color`'s bit depth does not match `bit_depth`, returns `InvalidPixelDepth` error.
+    pub fn new_with_color(image_type: TgaImageType, width: u16, height: u16, bit_depth: u8, color: TgaColor) -> Result<TgaImage, TgaError> {
+        let mut image = TgaImage::new(image_type, width, height, bit_depth)?;
+        image.check_color(color)?;
+
+        // Fill the pixel data with the color
+        for pixel in image.data.chunks_exact_mut(color.as_slice().len()) {
+            pixel.copy_from_slice(color.as_slice());
+        }
+
+        Ok(image)
+    }
+
+    /// Tries to read a TGA image from a byte buffer.
+    /// 
+    /// The pixel data of run-length encoded image types is decoded, so pixels can be accessed the same way for every image type. The descriptor's reserved bits 6-7 are cleared, as some legacy writers use them for interleaving.
+    /// 
+    /// # Errors
+    /// If the buffer is not large enough to contain a TGA header, returns `InvalidSize` error.
+    /// 
+    /// If the header declares a color map without entries, or a color map is declared or required and its bit depth is not 15, 16, 24, or 32, returns `InvalidColorMap` error.
+    /// 
+    /// If the buffer is not large enough to contain the declared color map, returns `InvalidColorMap` error.
+    /// 
+    /// If the buffer is not large enough to contain the TGA image size read from the header, or the run-length encoded pixel data ends before every pixel is decoded, returns `InvalidSize` error.
+    /// 
+    /// If the image is color-mapped and its indices are not 8 or 16 bits, returns `UnsupportedIndexSize` error.
     /// 
     /// If the bit depth is invalid for the image type, returns `InvalidPixelDepth` error.
-    pub fn from_file<P: AsRef<Path>>(&self, filename: P) -> Result<TgaImage, TgaError> {
-        // Open file and read into buffer
-        let mut file = File::open(filename).map_err(|e| {FileOpen(e)})?;
-        let mut buf = vec![];
-        let size = file.read_to_end(&mut buf).map_err(|e| {FileRead(e)})?;
-        if size < HEADER_SIZE {
+    /// 
+    /// If the extension or developer area referenced by a TGA 2.0 footer does not fit in the buffer, returns `InvalidSize` error.
+    pub fn from_bytes(buf: &[u8]) -> Result<TgaImage, TgaError> {
+        let (image, _) = TgaImage::parse(buf, DecodeOptions::default())?;
+        Ok(image)
+    }
+
+    /// Tries to read a TGA image from a byte buffer, checking it as strictly as `options` requires.
+    /// 
+    /// With `Strictness::Standard`, this is the same as `from_bytes`. `Strictness::Strict` also rejects headers whose fields contradict each other or that set the descriptor's reserved bits. `Strictness::Lenient` instead drops an unusable color map from image types that are not color-mapped, clears alpha bits that do not fit the bit depth, fills pixel data that is cut off with zeros, and ignores extension and developer areas that do not fit in the buffer.
+    /// 
+    /// # Errors
+    /// If `options.strictness` is `Strict` and either of the descriptor's reserved bits 6-7 is set, returns `InvalidArgument` error.
+    /// 
+    /// If `options.strictness` is `Strict` and a color map is declared for an image type that is not color-mapped, or is not declared for one that is, or an undeclared color map has entries, returns `InvalidColorMap` error.
+    /// 
+    /// If `options.strictness` is `Strict` and the descriptor's alpha bits do not fit the bit depth, returns `InvalidPixelDepth` error.
+    /// 
+    /// If `options.strictness` is `Lenient` and the header's image size is more than 128 times the size of the pixel data in the buffer, returns `InvalidSize` error.
+    /// 
+    /// If `options.strictness` is `Lenient` and the padded pixel data could not be allocated, returns `AllocationFailed` error.
+    /// 
+    /// Otherwise, returns the same errors as `from_bytes`, except for those that lenient decoding repairs.
+    pub fn from_bytes_with_options(buf: &[u8], options: DecodeOptions) -> Result<TgaImage, TgaError> {
+        let (image, _) = TgaImage::parse(buf, options)?;
+        Ok(image)
+    }
+
+    /// Tries to read a TGA image from a byte buffer, along with the offset of the end of its pixel data.
+    fn parse(buf: &[u8], options: DecodeOptions) -> Result<(TgaImage, usize), TgaError> {
+        if buf.len() < HEADER_SIZE {
             return Err(InvalidSize);
         }
+        let lenient = options.strictness == Strictness::Lenient;
 
-        // Copy header from file
-        let header_buf: [u8; HEADER_SIZE] = buf[0..HEADER_SIZE].try_into().map_err(|_| {InvalidSize})?;
-        let header = TgaHeader::from_buf(header_buf)?;
+        // Copy header from buffer
+        let mut header_buf: [u8; HEADER_SIZE] = buf[0..HEADER_SIZE].try_into().map_err(|_| {InvalidSize})?;
+        if options.strictness != Strictness::Strict {
+            // Clear the descriptor's reserved bits, which some legacy writers use for interleaving
+            header_buf[17] &= !DESCRIPTOR_RESERVED;
+        }
+        let mut header = TgaHeader::from_buf(header_buf)?;
 
-        // Ensure file size is large enough to contain all data specified in the header
-        if size < header.file_size() {
-            return Err(InvalidSize);
+        // The declared color map is skipped even if lenient decoding drops it
+        let color_map_start = HEADER_SIZE + header.id_size as usize;
+        let data_start = color_map_start + header.color_map_byte_size();
+        match options.strictness {
+            Strictness::Strict => header.check_consistency()?,
+            Strictness::Standard => {}
+            Strictness::Lenient => header.repair()
         }
 
-        // Ensure the pixel depth is valid
-        if !header.image_type.valid_depth(header.image_bit_depth) {
-            return Err(InvalidPixelDepth);
+        // Ensure the color map is valid and fits in the buffer
+        header.check_color_map()?;
+        if buf.len() < data_start {
+            return Err(InvalidColorMap);
+        }
+
+        // Ensure buffer size is large enough to contain all data specified in the header
+        if !lenient && !header.image_type.is_rle() && buf.len() < data_start.saturating_add(header.image_size()) {
+            return Err(InvalidSize);
         }
 
+        header.check_pixel_depth()?;
+
         // Read image id, color map, and image data
-        let mut idx = HEADER_SIZE;
-        let id = buf[idx..idx + header.id_size as usize].to_vec().into_boxed_slice();
-        idx += header.id_size as usize;
-        let color_map = buf[idx..idx + header.color_map_size as usize].to_vec().into_boxed_slice();
-        idx += header.color_map_size as usize;
-        let data = buf[idx..idx + image_size(header.width, header.height, header.image_bit_depth)].to_vec().into_boxed_slice();
+        let id = buf[HEADER_SIZE..color_map_start].to_vec().into_boxed_slice();
+        let color_map = buf[color_map_start..color_map_start + header.color_map_byte_size()].to_vec().into_boxed_slice();
+        let (data, data_end) = if lenient {
+            let (data, size) = rle::decode_data_padded(&buf[data_start..], &header)?;
+            (data, data_start + size)
+        } else if header.image_type.is_rle() {
+            let (data, size) = rle::decode_data(&buf[data_start..], &header)?;
+            (data, data_start + size)
+        } else {
+            (buf[data_start..data_start + header.image_size()].to_vec(), data_start + header.image_size())
+        };
 
-        Ok(TgaImage {
+        // Read extension and developer areas if the footer references them
+        let (extension_offset, developer_offset) = footer::parse_footer(buf).unwrap_or((0, 0));
+        let extension = match extension_offset {
+            0 => None,
+            offset => match extension::parse_extension_area(buf, offset as usize) {
+                Ok(extension) => Some(extension),
+                Err(_) if lenient => None,
+                Err(e) => return Err(e)
+            }
+        };
+        let developer_tags = match developer_offset {
+            0 => vec![],
+            offset => match developer::parse_developer_area(buf, offset as usize) {
+                Ok(tags) => tags,
+                Err(_) if lenient => vec![],
+                Err(e) => return Err(e)
+            }
+        };
+
+        let image = TgaImage {
             header,
-            state: TgaImageState::Uncompressed,
+            state: TgaImageState::decoded(header.image_type),
             id,
             color_map,
-            data
-        })
+            data: data.into_boxed_slice(),
+            developer_tags,
+            extension
+        };
+        Ok((image, data_end))
     }
-    
-    pub fn set_pixel(&mut self, x: u16, y: u16, color: TgaColor) -> Result<(), TgaError> {
-        // Ensure that the pixel coordinate is valid for this image
-        if self.header.width <= x || self.header.height <= y {
-            return Err(InvalidCoordinate);
-        }
 
-        // Ensure the color is valid for this image
-        if !self.header.image_type.valid_color(color) {
-            return Err(InvalidColor);
+    /// Tries to read a TGA image from a byte buffer that contains nothing else.
+    /// 
+    /// This is the same as `from_bytes`, except that data after the image is rejected. If the buffer ends with a TGA 2.0 footer, the extension and developer areas it references and the footer itself are part of the image.
+    /// 
+    /// # Errors
+    /// If the buffer is larger than the image, returns `InvalidSize` error.
+    /// 
+    /// Otherwise, returns the same errors as `from_bytes`.
+    pub fn from_bytes_exact(buf: &[u8]) -> Result<TgaImage, TgaError> {
+        let (image, data_end) = TgaImage::parse(buf, DecodeOptions::default())?;
+
+        // Find the end of the image, including any areas referenced by the footer
+        let mut end = data_end;
+        if let Some((extension_offset, developer_offset)) = footer::parse_footer(buf) {
+            if extension_offset != 0 {
+                let offset = extension_offset as usize;
+                let size_buf = buf.get(offset..offset + 2).ok_or(InvalidSize)?;
+                let size = u16::from_le_bytes(size_buf.try_into().map_err(|_| {InvalidSize})?) as usize;
+                end = end.max(offset + size);
+            }
+            if developer_offset != 0 {
+                end = end.max(developer::developer_area_end(buf, developer_offset as usize)?);
+            }
+            end += footer::FOOTER_SIZE;
         }
 
-        // Ensure the color's pixel depth is valid for this image
-        let bit_depth = color.bit_depth();
-        if !self.header.image_type.valid_depth(bit_depth) || bit_depth != self.header.image_bit_depth {
-            return Err(InvalidPixelDepth);
+        // Ensure there is no trailing data
+        if buf.len() > end {
+            return Err(InvalidSize);
         }
 
-        // Set pixel to color
-        let byte_depth = (bit_depth / 8) as u16;
-        let start = (x + y * self.header.width) * byte_depth;
-        let end = start + byte_depth;
-        let start = start as usize;
-        let end = end as usize;
-        self.data[start..end].copy_from_slice(color.as_slice());
+        Ok(image)
+    }
 
-        Ok(())
+    /// Tries to read a TGA image from `reader`.
+    /// 
+    /// The reader is read to its end, and the data is parsed as in `from_bytes`, so it may contain a TGA 2.0 footer.
+    /// 
+    /// # Errors
+    /// If the data could not be read, returns `FileRead` error.
+    /// 
+    /// If the data is invalid, returns the same errors as `from_bytes`.
+    pub fn from_reader<R: Read>(reader: &mut R) -> Result<TgaImage, TgaError> {
+        let mut buf = vec![];
+        reader.read_to_end(&mut buf).map_err(|e| {FileRead(e, None)})?;
+
+        TgaImage::from_bytes(&buf)
     }
 
-    pub fn to_file<P: AsRef<Path>>(&self, filename: P) -> Result<(), TgaError> {
-        // Allocate buffer to write
-        let mut buf = vec![0; self.header.file_size()].into_boxed_slice();
-
-        // Copy header and all data to buffer
-        let id_size = self.header.id_size as usize;
-        let color_map_size = self.header.color_map_size as usize;
-        let image_size = self.header.image_size();
-        buf[0..HEADER_SIZE].copy_from_slice(&self.header.to_buf());
-        let mut idx = HEADER_SIZE;
-        buf[idx..idx + id_size].copy_from_slice(&self.id);
-        idx += id_size;
-        buf[idx..idx + color_map_size].copy_from_slice(&self.color_map);
-        idx += color_map_size;
-        buf[idx..idx + image_size].copy_from_slice(&self.data);
-
-        // Create file and write buffer
-        let mut file = File::create(filename).map_err(|e| {FileOpen(e)})?;
-        file.write_all(&buf).map_err(|e| {FileWrite(e)})?;
+    /// Tries to read a TGA image from a file.
+    /// 
+    /// See `from_bytes` for how the file is parsed.
+    /// 
+    /// # Errors
+    /// If the file could not be opened, returns `FileOpen` error.
+    /// 
+    /// If the file could not be read, returns `FileRead` error.
+    /// 
+    /// If the file's contents are invalid, returns the same errors as `from_bytes`.
+    pub fn from_file<P: AsRef<Path>>(filename: P) -> Result<TgaImage, TgaError> {
+        TgaImage::from_bytes(&read_file(filename.as_ref())?)
+    }
 
-        Ok(())
+    /// Tries to read a TGA image from a file, checking it as strictly as `options` requires.
+    /// 
+    /// See `from_bytes_with_options` for how the file is parsed.
+    /// 
+    /// # Errors
+    /// If the file could not be opened, returns `FileOpen` error.
+    /// 
+    /// If the file could not be read, returns `FileRead` error.
+    /// 
+    /// If the file's contents are invalid, returns the same errors as `from_bytes_with_options`.
+    pub fn from_file_with_options<P: AsRef<Path>>(filename: P, options: DecodeOptions) -> Result<TgaImage, TgaError> {
+        TgaImage::from_bytes_with_options(&read_file(filename.as_ref())?, options)
     }
-}
 
-fn image_size(width: u16, height: u16, bit_depth: u8) -> usize {
-    return width as usize * height as usize * (bit_depth as usize / 8)
+    /// Tries to read a TGA image from a file, along with the file's TGA version.
+    /// 
+    /// The version is `V2` if the file ends with a valid TGA 2.0 footer, and `V1` otherwise. See `from_bytes` for how the file is parsed.
+    /// 
+    /// # Errors
+    /// If the file could not be opened, returns `FileOpen` error.
+    /// 
+    /// If the file could not be read, returns `FileRead` error.
+    /// 
+    /// If the file's contents are invalid, returns the same errors as `from_bytes`.
+    pub fn from_file_versioned<P: AsRef<Path>>(filename: P) -> Result<(TgaImage, TgaVersion), TgaError> {
+        let buf = read_file(filename.as_ref())?;
+        let image = TgaImage::from_bytes(&buf)?;
+        let version = match footer::parse_footer(&buf) {
+            Some(_) => TgaVersion::V2,
+            None => TgaVersion::V1
+        };
+        Ok((image, version))
+    }
+    
+    /// Tries to read a sequence of TGA images, such as the frames of an animation, from files.
+    /// 
+    /// Every image must have the same dimensions and bit depth as the first.
+    /// 
+    /// # Errors
+    /// If a file could not be read, returns `InvalidFrame` error with the index of the failing path and the error returned by `from_file`.
+    /// 
+    /// If an image's dimensions or bit depth differ from the first image, returns `InvalidSize` error.
+    pub fn load_many<P: AsRef<Path>>(paths: &[P]) -> Result<Vec<TgaImage>, TgaError> {
+        let mut images: Vec<TgaImage> = Vec::with_capacity(paths.len());
+        for (index, path) in paths.iter().enumerate() {
+            let image = TgaImage::from_file(path).map_err(|e| {InvalidFrame(index, Box::new(e))})?;
+
+            // Ensure every frame matches the first
+            if let Some(first) = images.first() {
+                if (image.header.width, image.header.height, image.header.image_bit_depth) != (first.header.width, first.header.height, first.header.image_bit_depth) {
+                    return Err(InvalidSize);
+                }
+            }
+            images.push(image);
+        }
+
+        Ok(images)
+    }
+
+    /// Tries to read only the header of a TGA image file.
+    /// 
+    /// This is the same as `TgaHeader::from_file`, and returns the same errors.
+    pub fn header_from_file<P: AsRef<Path>>(filename: P) -> Result<TgaHeader, TgaError> {
+        TgaHeader::from_file(filename)
+    }
+
+    /// Tries to read the `w`x`h` rectangle at (`x`, `y`) of a TGA image file.
+    /// 
+    /// The result is the same as loading the whole file and calling `crop`, except that the extension and developer areas are not read. For uncompressed images, only the rows intersecting the rectangle are read from the file. Run-length encoded images are decoded in full and then cropped.
+    /// 
+    /// # Errors
+    /// If the file could not be opened, returns `FileOpen` error.
+    /// 
+    /// If the file could not be read, returns `FileRead` error.
+    /// 
+    /// If the file's header is invalid, returns the same errors as `from_bytes`.
+    /// 
+    /// If `w` or `h` is zero, or the file ends before the pixel data, returns `InvalidSize` error.
+    /// 
+    /// If the rectangle is not fully inside the image, returns `InvalidCoordinate` error.
+    pub fn from_file_region<P: AsRef<Path>>(filename: P, x: u16, y: u16, w: u16, h: u16) -> Result<TgaImage, TgaError> {
+        let filename = filename.as_ref();
+        let mut file = open_file(filename)?;
+        let mut header_buf = [0; HEADER_SIZE];
+        rle::read_exact(&mut file, &mut header_buf).map_err(|e| {e.with_path(filename)})?;
+
+        // Clear the descriptor's reserved bits, the same as `from_bytes`
+        header_buf[17] &= !DESCRIPTOR_RESERVED;
+        let header = TgaHeader::from_buf(header_buf)?;
+
+        // Run-length encoded rows can only be found by decoding from the start
+        if header.image_type.is_rle() {
+            let image = TgaImage::from_file(filename)?;
+            return image.crop(x, y, w, h);
+        }
+
+        header.check_color_map()?;
+        header.check_pixel_depth()?;
+        if w == 0 || h == 0 {
+            return Err(InvalidSize);
+        }
+        if x as u32 + w as u32 > header.width as u32 || y as u32 + h as u32 > header.height as u32 {
+            return Err(InvalidCoordinate);
+        }
+
+        // Read image id and color map
+        let mut id = vec![0; header.id_size as usize];
+        rle::read_exact(&mut file, &mut id).map_err(|e| {e.with_path(filename)})?;
+        let mut color_map = vec![0; header.color_map_byte_size()];
+        rle::read_exact(&mut file, &mut color_map).map_err(|e| {e.with_path(filename)})?;
+
+        let mut region_header = header;
+        region_header.width = w;
+        region_header.height = h;
+        let mut image = TgaImage {
+            header: region_header,
+            state: TgaImageState::decoded(header.image_type),
+            id: id.into_boxed_slice(),
+            color_map: color_map.into_boxed_slice(),
+            data: vec![0; region_header.image_size()].into_boxed_slice(),
+            developer_tags: vec![],
+            extension: None
+        };
+
+        // Read the part of each stored row that intersects the rectangle
+        let byte_depth = image.byte_depth();
+        let data_start = (HEADER_SIZE + header.id_size as usize + header.color_map_byte_size()) as u64;
+        let row_size = header.width as u64 * byte_depth as u64;
+        let right = header.descriptor & DESCRIPTOR_RIGHT != 0;
+        let top = header.descriptor & DESCRIPTOR_TOP != 0;
+        let first_column = if right { header.width - x - w } else { x };
+        let mut row = vec![0; w as usize * byte_depth];
+        for dy in 0..h {
+            let stored_row = if top { y + dy } else { header.height - 1 - (y + dy) };
+            let offset = data_start + stored_row as u64 * row_size + first_column as u64 * byte_depth as u64;
+            file.seek(SeekFrom::Start(offset)).map_err(|e| {FileRead(e, Some(filename.to_path_buf()))})?;
+            rle::read_exact(&mut file, &mut row).map_err(|e| {e.with_path(filename)})?;
+
+            for dx in 0..w {
+                let column = if right { header.width - 1 - (x + dx) } else { x + dx };
+                let src = (column - first_column) as usize * byte_depth;
+                let dest = image.pixel_offset(dx, dy);
+                image.data[dest..dest + byte_depth].copy_from_slice(&row[src..src + byte_depth]);
+            }
+        }
+
+        Ok(image)
+    }
+
+    /// Returns the raw pixel data.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the raw pixel data for editing.
+    /// 
+    /// The length of the data is managed by the image, but the caller is responsible for keeping its contents consistent with the header. Pixels are stored in the format given by the header's image type and bit depth.
+    pub fn data_mut(&mut self) -> &mut [u8] {
+        &mut self.data
+    }
+
+    /// Tries to clone the image without aborting if memory runs out.
+    /// 
+    /// The derived `Clone` aborts the process when an allocation fails, which is a concern for very large images.
+    /// 
+    /// # Errors
+    /// If any of the image's buffers could not be allocated, returns `AllocationFailed` error.
+    pub fn try_clone(&self) -> Result<TgaImage, TgaError> {
+        let mut developer_tags = vec![];
+        developer_tags.try_reserve_exact(self.developer_tags.len()).map_err(|_| {AllocationFailed})?;
+        for tag in &self.developer_tags {
+            developer_tags.push(DeveloperTag { id: tag.id, data: try_copy(&tag.data)?.into_vec() });
+        }
+
+        Ok(TgaImage {
+            header: self.header,
+            state: self.state,
+            id: try_copy(&self.id)?,
+            color_map: try_copy(&self.color_map)?,
+            data: try_copy(&self.data)?,
+            developer_tags,
+            extension: match &self.extension {
+                Some(extension) => Some(try_copy(extension)?),
+                None => None
+            }
+        })
+    }
+
+    /// Reallocates the image id, color map, and pixel data to exactly the sizes declared in the header.
+    /// 
+    /// Buffers longer than their declared size are truncated, and shorter buffers are padded with zeros, so `verify` only fails afterwards if the header itself is invalid.
+    pub fn shrink(&mut self) {
+        fn fit(buf: &mut Box<[u8]>, size: usize) {
+            let mut vec = std::mem::take(buf).into_vec();
+            vec.resize(size, 0);
+            vec.shrink_to_fit();
+            *buf = vec.into_boxed_slice();
+        }
+
+        fit(&mut self.id, self.header.id_size as usize);
+        fit(&mut self.color_map, self.header.color_map_byte_size());
+        fit(&mut self.data, self.header.image_size());
+    }
+
+    /// Tries to verify that the image's buffers match the sizes declared in its header.
+    /// 
+    /// # Errors
+    /// If the image id's length is not `id_size`, returns `InvalidSize` error.
+    /// 
+    /// If the header declares an invalid color map, as described in `from_bytes`, returns `InvalidColorMap` error.
+    /// 
+    /// If the color map's length is not the declared color map size, returns `InvalidColorMap` error.
+    /// 
+    /// If the pixel data's length is not the declared image size, returns `InvalidSize` error.
+    pub fn verify(&self) -> Result<(), TgaError> {
+        if self.id.len() != self.header.id_size as usize {
+            return Err(InvalidSize);
+        }
+        self.header.check_color_map()?;
+        if self.color_map.len() != self.header.color_map_byte_size() {
+            return Err(InvalidColorMap);
+        }
+        if self.data.len() != self.header.image_size() {
+            return Err(InvalidSize);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the number of pixels in the image.
+    pub fn pixel_count(&self) -> usize {
+        self.header.width as usize * self.header.height as usize
+    }
+
+    /// Returns the length of the pixel data in bytes.
+    pub fn byte_len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns an iterator over the rows of pixel data, in storage order.
+    pub fn rows(&self) -> impl Iterator<Item = &[u8]> {
+        self.data.chunks_exact(self.row_size().max(1))
+    }
+
+    /// Returns an iterator over the rows of pixel data for editing, in storage order.
+    pub fn rows_mut(&mut self) -> impl Iterator<Item = &mut [u8]> {
+        let row_size = self.row_size().max(1);
+        self.data.chunks_exact_mut(row_size)
+    }
+
+    /// Returns the tags of the image's developer area.
+    /// 
+    /// This is empty if the image was read without a TGA 2.0 footer or developer area and no tags were set.
+    pub fn developer_tags(&self) -> &[DeveloperTag] {
+        &self.developer_tags
+    }
+
+    /// Returns a stable hash of the image's pixel data.
+    /// 
+    /// The hash is computed with 64-bit FNV-1a, so it is the same across runs and platforms. Only the pixel data is hashed; the header, id, and color map are not.
+    pub fn data_hash(&self) -> u64 {
+        fnv1a(&self.data)
+    }
+
+    /// Returns a stable hash of each row of pixel data, in storage order.
+    /// 
+    /// Each row is hashed like `data_hash`, so comparing the hashes of two versions of an image shows which rows changed.
+    pub fn scanline_hashes(&self) -> Vec<u64> {
+        self.rows().map(fnv1a).collect()
+    }
+
+    /// Tries to check whether the image uses at most `max` distinct pixel values.
+    /// 
+    /// For color-mapped images, the color map indices are compared. The scan stops as soon as more than `max` values are found, so this is cheaper than counting every color.
+    /// 
+    /// # Errors
+    /// If the image's bit depth does not match a color format, returns `InvalidPixelDepth` error.
+    pub fn has_at_most_colors(&self, max: usize) -> Result<bool, TgaError> {
+        // Ensure the pixel depth matches a color format
+        let byte_depth = self.byte_depth();
+        if !(1..=4).contains(&byte_depth) {
+            return Err(InvalidPixelDepth);
+        }
+
+        let mut colors = HashSet::new();
+        for pixel in self.data.chunks_exact(byte_depth) {
+            if colors.insert(pixel) && colors.len() > max {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Tries to get the color of the pixel at (`x`, `y`).
+    /// 
+    /// The returned color variant is determined by the image's bit depth, and 16-bit greyscale pixels are returned as `GreyscaleAlpha`.
+    /// 
+    /// # Errors
+    /// If the coordinate is outside of the image, returns `InvalidCoordinate` error.
+    /// 
+    /// If the image's bit depth does not match a color format, returns `InvalidPixelDepth` error.
+    pub fn get_pixel(&self, x: u16, y: u16) -> Result<TgaColor, TgaError> {
+        // Ensure that the pixel coordinate is valid for this image
+        let start = self.byte_offset(x, y).ok_or(InvalidCoordinate)?;
+
+        // Ensure the pixel depth matches a color format
+        let byte_depth = self.byte_depth();
+        if !(1..=4).contains(&byte_depth) {
+            return Err(InvalidPixelDepth);
+        }
+
+        // Copy pixel into a color of the matching format
+        self.color_from_pixel(&self.data[start..start + byte_depth])
+    }
+
+    /// Tries to read the bytes of a single pixel as a color, using `GreyscaleAlpha` for 16-bit greyscale pixels.
+    fn color_from_pixel(&self, pixel: &[u8]) -> Result<TgaColor, TgaError> {
+        match TgaColor::from_slice(pixel)? {
+            RGB16(bytes) if self.header.image_type.is_greyscale() => Ok(GreyscaleAlpha(bytes)),
+            color => Ok(color)
+        }
+    }
+
+    /// Tries to create an iterator over the pixels in the `w`x`h` rectangle at (`x`, `y`).
+    /// 
+    /// Each item is a pixel's coordinate and color. Pixels are visited row by row from the top-left corner of the rectangle, without copying the image.
+    /// 
+    /// # Errors
+    /// If the rectangle is not fully inside the image, returns `InvalidCoordinate` error.
+    /// 
+    /// If the image's bit depth does not match a color format, returns `InvalidPixelDepth` error.
+    pub fn pixels_in_rect(&self, x: u16, y: u16, w: u16, h: u16) -> Result<impl Iterator<Item = (u16, u16, TgaColor)> + '_, TgaError> {
+        self.check_rect(x, y, w, h)?;
+
+        // Choose the color format once, so reading each pixel cannot fail
+        let byte_depth = self.byte_depth();
+        let color_of: fn(&[u8]) -> TgaColor = match byte_depth {
+            1 => |p| Greyscale([p[0]]),
+            2 if self.header.image_type.is_greyscale() => |p| GreyscaleAlpha([p[0], p[1]]),
+            2 => |p| RGB16([p[0], p[1]]),
+            3 => |p| RGB24([p[0], p[1], p[2]]),
+            4 => |p| RGBA([p[0], p[1], p[2], p[3]]),
+            _ => return Err(InvalidPixelDepth)
+        };
+
+        Ok((y..y + h).flat_map(move |py| (x..x + w).map(move |px| (px, py))).map(move |(px, py)| {
+            let start = self.pixel_offset(px, py);
+            (px, py, color_of(&self.data[start..start + byte_depth]))
+        }))
+    }
+
+    /// Tries to set the pixel at (`x`, `y`) to `color`.
+    /// 
+    /// # Errors
+    /// If the coordinate is outside of the image, returns `InvalidCoordinate` error.
+    /// 
+    /// If the color is invalid for this image, returns `InvalidColor` error.
+    /// 
+    /// If the color's bit depth does not match this image, returns `InvalidPixelDepth` error.
+    pub fn set_pixel(&mut self, x: u16, y: u16, color: TgaColor) -> Result<(), TgaError> {
+        // Ensure that the pixel coordinate is valid for this image
+        let start = self.byte_offset(x, y).ok_or(InvalidCoordinate)?;
+
+        self.check_color(color)?;
+
+        // Set pixel to color
+        let end = start + color.as_slice().len();
+        self.data[start..end].copy_from_slice(color.as_slice());
+
+        Ok(())
+    }
+
+    /// Sets the pixel at (`x`, `y`) to `color` without checking the coordinate or color.
+    /// 
+    /// This is intended for hot loops that have already validated their geometry and colors. Debug builds still check both and panic on misuse.
+    /// 
+    /// # Safety
+    /// The caller must ensure that (`x`, `y`) is inside the image and that `color` is valid for this image, as `set_pixel` would check. Otherwise, this writes outside of the pixel data.
+    pub unsafe fn set_pixel_unchecked(&mut self, x: u16, y: u16, color: TgaColor) {
+        debug_assert!(self.contains(x, y), "pixel coordinate outside of image");
+        debug_assert!(self.check_color(color).is_ok(), "color does not match image format");
+
+        let start = self.pixel_offset(x, y);
+        let bytes = color.as_slice();
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), self.data.as_mut_ptr().add(start), bytes.len());
+    }
+
+    /// Returns true if (`x`, `y`) is inside the image.
+    pub fn contains(&self, x: u16, y: u16) -> bool {
+        x < self.header.width && y < self.header.height
+    }
+
+    /// Returns the offset of the pixel at (`x`, `y`) into the pixel data, or `None` if the coordinate is outside of the image.
+    /// 
+    /// The offset respects the origin bits of the descriptor, so (0, 0) is always the top-left pixel as displayed.
+    pub fn byte_offset(&self, x: u16, y: u16) -> Option<usize> {
+        if !self.contains(x, y) {
+            return None;
+        }
+
+        Some(self.pixel_offset(x, y))
+    }
+
+    /// Returns the TGA image as a byte buffer.
+    /// 
+    /// Includes the header, id, color map, and pixel data. If the image has an extension area or developer tags, they are written after the pixel data, followed by a TGA 2.0 footer.
+    /// 
+    /// The pixel data of run-length encoded image types is encoded one row at a time in storage order, so the header's descriptor and origin bits still describe the encoded rows.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes_as(self.header.image_type, self.version())
+    }
+
+    /// Returns the TGA image as a byte buffer of the TGA version `version`.
+    /// 
+    /// `V2` images always end with a TGA 2.0 footer, even without an extension area, for tools that require the footer's signature. `V1` images end after the pixel data, so the extension area and developer tags are not written. Otherwise, this is the same as `to_bytes`.
+    pub fn to_bytes_versioned(&self, version: TgaVersion) -> Vec<u8> {
+        self.to_bytes_as(self.header.image_type, version)
+    }
+
+    /// Returns the TGA image as a byte buffer, as described in `to_bytes_versioned`, with the header's image type replaced by `image_type`.
+    fn to_bytes_as(&self, image_type: TgaImageType, version: TgaVersion) -> Vec<u8> {
+        let mut header = self.header;
+        header.image_type = image_type;
+
+        let mut buf = Vec::with_capacity(header.file_size());
+        buf.extend_from_slice(&header.to_buf());
+        buf.extend_from_slice(&self.id);
+        buf.extend_from_slice(&self.color_map);
+        if image_type.is_rle() && self.byte_depth() > 0 {
+            for row in self.rows() {
+                rle::encode_row(row, self.byte_depth(), &mut buf);
+            }
+        } else {
+            buf.extend_from_slice(&self.data);
+        }
+
+        // Write extension area, developer area, and footer
+        if version == TgaVersion::V2 {
+            let mut extension_offset = 0;
+            if let Some(extension) = &self.extension {
+                extension_offset = extension::write_extension_area(&mut buf, extension);
+            }
+            let mut developer_offset = 0;
+            if !self.developer_tags.is_empty() {
+                developer_offset = developer::write_developer_area(&mut buf, &self.developer_tags);
+            }
+            buf.extend_from_slice(&extension_offset.to_le_bytes());
+            buf.extend_from_slice(&developer_offset.to_le_bytes());
+            buf.extend_from_slice(footer::SIGNATURE);
+        }
+
+        buf
+    }
+
+    /// Returns the TGA version that `to_bytes` writes, which is `V2` only if the image has an extension area or developer tags.
+    fn version(&self) -> TgaVersion {
+        if self.extension.is_some() || !self.developer_tags.is_empty() {
+            TgaVersion::V2
+        } else {
+            TgaVersion::V1
+        }
+    }
+
+    /// Tries to write the TGA image to a file of the TGA version `version`.
+    /// 
+    /// The file contains the same bytes as `to_bytes_versioned`.
+    /// 
+    /// # Errors
+    /// If the file could not be created, returns `FileOpen` error.
+    /// 
+    /// If the file could not be written, returns `FileWrite` error.
+    pub fn to_file_versioned<P: AsRef<Path>>(&self, filename: P, version: TgaVersion) -> Result<(), TgaError> {
+        write_file(filename.as_ref(), &self.to_bytes_versioned(version))
+    }
+
+    /// Returns the TGA image as a byte buffer with its rows stored from the top-left corner if `top` is true, or the bottom-left corner otherwise.
+    /// 
+    /// The written descriptor's origin bits are set to match, and the stored rows are reordered so the image looks the same when displayed. The image itself is not changed. Otherwise, this is the same as `to_bytes`.
+    pub fn to_bytes_with_origin(&self, top: bool) -> Vec<u8> {
+        let mut image = self.clone();
+        image.set_right_origin(false);
+        image.set_top_origin(top);
+        image.to_bytes()
+    }
+
+    /// Tries to write the TGA image to a file with its rows stored from the top-left corner if `top` is true, or the bottom-left corner otherwise.
+    /// 
+    /// The file contains the same bytes as `to_bytes_with_origin`.
+    /// 
+    /// # Errors
+    /// If the file could not be created, returns `FileOpen` error.
+    /// 
+    /// If the file could not be written, returns `FileWrite` error.
+    pub fn to_file_with_origin<P: AsRef<Path>>(&self, filename: P, top: bool) -> Result<(), TgaError> {
+        write_file(filename.as_ref(), &self.to_bytes_with_origin(top))
+    }
+
+    /// Tries to write the TGA image to `writer`.
+    /// 
+    /// The same bytes as `to_bytes` are written.
+    /// 
+    /// # Errors
+    /// If the image could not be written, returns `FileWrite` error.
+    pub fn to_writer<W: Write>(&self, writer: &mut W) -> Result<(), TgaError> {
+        writer.write_all(&self.to_bytes()).map_err(|e| {FileWrite(e, None)})
+    }
+
+    /// Tries to write the TGA image to a file.
+    /// 
+    /// The file contains the same bytes as `to_bytes`.
+    /// 
+    /// # Errors
+    /// If the file could not be created, returns `FileOpen` error.
+    /// 
+    /// If the file could not be written, returns `FileWrite` error.
+    pub fn to_file<P: AsRef<Path>>(&self, filename: P) -> Result<(), TgaError> {
+        write_file(filename.as_ref(), &self.to_bytes())
+    }
+
+    /// Tries to write the TGA image to a file without ever leaving a partially written file at `filename`.
+    /// 
+    /// The image is written to a temporary file in the same directory, synced to disk, and then renamed over `filename`. Each call uses its own temporary file, so concurrent writes to the same destination do not interfere. The temporary file is removed if writing or renaming fails, and errors carry the path `filename` rather than the temporary file's path.
+    /// 
+    /// # Errors
+    /// If the temporary file could not be created, returns `FileOpen` error.
+    /// 
+    /// If the temporary file could not be written, synced, or renamed, returns `FileWrite` error.
+    pub fn to_file_atomic<P: AsRef<Path>>(&self, filename: P) -> Result<(), TgaError> {
+        let filename = filename.as_ref();
+
+        // Name the temporary file after the target so it stays in the same directory, and make it unique to this call
+        let count = TEMP_FILE_COUNT.fetch_add(1, Ordering::Relaxed);
+        let mut temp_name = filename.file_name().unwrap_or_default().to_os_string();
+        temp_name.push(format!(".{}.{}.tmp", std::process::id(), count));
+        let temp_path = filename.with_file_name(temp_name);
+
+        let result = File::create(&temp_path).map_err(|e| {FileOpen(e, None)}).and_then(|mut file| {
+            file.write_all(&self.to_bytes()).map_err(|e| {FileWrite(e, None)})?;
+            file.flush().map_err(|e| {FileWrite(e, None)})?;
+            file.sync_all().map_err(|e| {FileWrite(e, None)})?;
+            std::fs::rename(&temp_path, filename).map_err(|e| {FileWrite(e, None)})
+        }).map_err(|e| {e.with_path(filename)});
+
+        // Ensure the temporary file does not outlive a failed write or rename
+        if result.is_err() {
+            let _ = std::fs::remove_file(&temp_path);
+        }
+
+        result
+    }
+
+    /// Tries to get the TGA image as a byte buffer with run-length encoded pixel data.
+    /// 
+    /// The written header has the run-length encoded version of the header's image type, and rows are encoded as in `to_bytes`. The image itself is not changed.
+    /// 
+    /// # Errors
+    /// If the image type is `NoImage`, returns `InvalidImageType` error.
+    /// 
+    /// If the bit depth is invalid for the image type, returns `InvalidPixelDepth` error.
+    pub fn to_bytes_rle(&self) -> Result<Vec<u8>, TgaError> {
+        let image_type = self.header.image_type.rle_type();
+        if !image_type.is_rle() {
+            return Err(InvalidImageType);
+        }
+        if !image_type.valid_depth(self.header.image_bit_depth) {
+            return Err(InvalidPixelDepth);
+        }
+
+        Ok(self.to_bytes_as(image_type, self.version()))
+    }
+
+    /// Tries to write the TGA image to a file with run-length encoded pixel data.
+    /// 
+    /// The file contains the same bytes as `to_bytes_rle`.
+    /// 
+    /// # Errors
+    /// If the file could not be created, returns `FileOpen` error.
+    /// 
+    /// If the file could not be written, returns `FileWrite` error.
+    /// 
+    /// Otherwise, returns the same errors as `to_bytes_rle`.
+    pub fn to_file_rle<P: AsRef<Path>>(&self, filename: P) -> Result<(), TgaError> {
+        write_file(filename.as_ref(), &self.to_bytes_rle()?)
+    }
+
+    /// Tries to write the TGA image to a file, run-length encoded only if that makes the pixel data smaller.
+    /// 
+    /// Returns the image type that was written, which is the header's image type with or without run-length encoding. The image itself is not changed.
+    /// 
+    /// # Errors
+    /// If the bit depth is invalid for the image type, returns `InvalidPixelDepth` error.
+    /// 
+    /// If the file could not be created, returns `FileOpen` error.
+    /// 
+    /// If the file could not be written, returns `FileWrite` error.
+    pub fn to_file_auto<P: AsRef<Path>>(&self, filename: P) -> Result<TgaImageType, TgaError> {
+        // Pick the smaller encoding of the pixel data
+        let base_type = self.header.image_type.base_type();
+        let image_type = if self.rle_encoded_size()? < self.header.image_size() { base_type.rle_type() } else { base_type };
+
+        write_file(filename.as_ref(), &self.to_bytes_as(image_type, self.version()))?;
+        Ok(image_type)
+    }
+}
+
+impl Default for TgaImage {
+    /// Returns a 1x1 black 24-bit `TrueColorImage`.
+    fn default() -> TgaImage {
+        let header = TgaHeader::default();
+
+        TgaImage {
+            header,
+            state: TgaImageState::Uncompressed,
+            id: vec![].into_boxed_slice(),
+            color_map: vec![].into_boxed_slice(),
+            data: vec![0; header.image_size()].into_boxed_slice(),
+            developer_tags: vec![],
+            extension: None
+        }
+    }
+}
+
+impl TgaImage {
+    /// Ensures `color` is valid for this image's type and bit depth.
+    fn check_color(&self, color: TgaColor) -> Result<(), TgaError> {
+        // Ensure the color is valid for this image
+        if !self.header.image_type.valid_color(color) {
+            return Err(InvalidColor);
+        }
+
+        // Ensure the color's pixel depth is valid for this image
+        if !self.header.image_type.valid_depth(color.bit_depth()) || color.byte_depth() as usize != self.byte_depth() {
+            return Err(InvalidPixelDepth);
+        }
+
+        Ok(())
+    }
+
+    /// Ensures the rectangle at (`x`, `y`) with size `w`x`h` is inside this image.
+    fn check_rect(&self, x: u16, y: u16, w: u16, h: u16) -> Result<(), TgaError> {
+        if x as u32 + w as u32 > self.header.width as u32 || y as u32 + h as u32 > self.header.height as u32 {
+            return Err(InvalidCoordinate);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the number of color channels that per-channel operations apply to.
+    /// 
+    /// This excludes the alpha channel of 32-bit images.
+    /// 
+    /// # Errors
+    /// If the image is not an 8-bit greyscale or 24/32-bit true color image, returns `InvalidImageType` error.
+    fn color_channels(&self) -> Result<usize, TgaError> {
+        let image_type = self.header.image_type;
+        match self.header.image_bit_depth {
+            8 if image_type.is_greyscale() => Ok(1),
+            24 | 32 if image_type.is_true_color() => Ok(3),
+            _ => Err(InvalidImageType)
+        }
+    }
+
+    /// Returns the number of bytes used by each pixel.
+    fn byte_depth(&self) -> usize {
+        (self.header.image_bit_depth as usize).div_ceil(8)
+    }
+
+    /// Returns the number of bytes in each row of pixels.
+    fn row_size(&self) -> usize {
+        self.header.width as usize * self.byte_depth()
+    }
+
+    /// Returns the offset of the pixel at (`x`, `y`) into the pixel data.
+    /// 
+    /// Does not check that the coordinate is inside the image.
+    fn pixel_offset(&self, x: u16, y: u16) -> usize {
+        // Map the displayed coordinate to the stored coordinate
+        let x = if self.header.descriptor & DESCRIPTOR_RIGHT != 0 { self.header.width - 1 - x } else { x };
+        let y = if self.header.descriptor & DESCRIPTOR_TOP != 0 { y } else { self.header.height - 1 - y };
+
+        (x as usize + y as usize * self.header.width as usize) * self.byte_depth()
+    }
+}
+
+/// Computes the 64-bit FNV-1a hash of `bytes`.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    bytes.iter().fold(OFFSET_BASIS, |hash, &byte| (hash ^ byte as u64).wrapping_mul(PRIME))
+}
+
+/// Tries to open the file at `filename` for reading.
+fn open_file(filename: &Path) -> Result<File, TgaError> {
+    File::open(filename).map_err(|e| {FileOpen(e, Some(filename.to_path_buf()))})
+}
+
+/// Tries to read the whole file at `filename` into a buffer.
+fn read_file(filename: &Path) -> Result<Vec<u8>, TgaError> {
+    let mut file = open_file(filename)?;
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).map_err(|e| {FileRead(e, Some(filename.to_path_buf()))})?;
+
+    Ok(buf)
+}
+
+/// Tries to create or truncate the file at `filename` and write `bytes` to it.
+fn write_file(filename: &Path, bytes: &[u8]) -> Result<(), TgaError> {
+    let mut file = File::create(filename).map_err(|e| {FileOpen(e, Some(filename.to_path_buf()))})?;
+    file.write_all(bytes).map_err(|e| {FileWrite(e, Some(filename.to_path_buf()))})
+}
+
+/// Tries to copy `bytes` into a new buffer, returning `AllocationFailed` error if it could not be allocated.
+fn try_copy(bytes: &[u8]) -> Result<Box<[u8]>, TgaError> {
+    let mut copy = vec![];
+    copy.try_reserve_exact(bytes.len()).map_err(|_| {AllocationFailed})?;
+    copy.extend_from_slice(bytes);
+    Ok(copy.into_boxed_slice())
+}
+
+/// Returns the size of an image's pixel data in bytes, or `None` if it does not fit in a `usize`.
+/// 
+/// Each pixel is rounded up to a whole number of bytes, so 15-bit pixels use 2 bytes.
+fn image_size(width: u16, height: u16, bit_depth: u8) -> Option<usize> {
+    (width as usize).checked_mul(height as usize)?.checked_mul((bit_depth as usize).div_ceil(8))
 }
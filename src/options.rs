@@ -0,0 +1,58 @@
+use crate::{TgaError, TgaHeader};
+use crate::TgaError::*;
+
+/// How strictly `TgaImage::from_bytes_with_options` checks the data it reads.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Strictness {
+    /// Rejects everything `Standard` does, and also headers whose fields contradict each other.
+    Strict,
+    /// Rejects data that cannot be read, the same as `from_bytes`.
+    #[default]
+    Standard,
+    /// Repairs or ignores problems that many legacy tools tolerate.
+    Lenient,
+}
+
+/// Options that control how a TGA image is read.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DecodeOptions {
+    /// How strictly the data is checked.
+    pub strictness: Strictness,
+}
+
+impl TgaHeader {
+    /// Ensures the header's fields agree with each other.
+    /// 
+    /// A color map must be declared exactly when the image type is color-mapped, an undeclared color map must have no entries, and the descriptor's alpha bits must fit the bit depth.
+    pub(crate) fn check_consistency(&self) -> Result<(), TgaError> {
+        if self.has_color_map != self.image_type.is_color_mapped() {
+            return Err(InvalidColorMap);
+        }
+        if !self.has_color_map && self.color_map_size != 0 {
+            return Err(InvalidColorMap);
+        }
+
+        let mut header = *self;
+        header.set_alpha_bits(self.alpha_bits())
+    }
+
+    /// Repairs header fields that contradict each other where the intent is clear.
+    /// 
+    /// An unusable color map of an image type that is not color-mapped is dropped, a color-mapped image type with color map entries is marked as having a color map, and alpha bits that do not fit the bit depth are cleared.
+    pub(crate) fn repair(&mut self) {
+        if self.image_type.is_color_mapped() {
+            if self.color_map_size != 0 {
+                self.has_color_map = true;
+            }
+        } else if self.check_color_map().is_err() {
+            self.has_color_map = false;
+            self.color_map_first_index = 0;
+            self.color_map_size = 0;
+            self.color_map_bit_depth = 0;
+        }
+
+        if self.set_alpha_bits(self.alpha_bits()).is_err() {
+            self.descriptor &= 0xf0;
+        }
+    }
+}
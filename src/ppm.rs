@@ -0,0 +1,107 @@
+use std::io::{Read, Write};
+
+use crate::{TgaColor, TgaError, TgaImage};
+use crate::TgaError::*;
+use crate::TgaImageType::*;
+
+impl TgaImage {
+    /// Tries to write the image to `writer` as a binary (P6) PPM image.
+    /// 
+    /// Pixels are written from the top-left corner in RGB order. Greyscale, 16-bit, and color-mapped images are converted to 24-bit RGB, and alpha is discarded.
+    /// 
+    /// # Errors
+    /// If a color-mapped pixel does not refer to an entry in the color map, returns `InvalidColor` error.
+    /// 
+    /// If the image's bit depth does not match a color format, returns `InvalidPixelDepth` error.
+    /// 
+    /// If the image could not be written, returns `FileWrite` error.
+    pub fn to_ppm<W: Write>(&self, writer: &mut W) -> Result<(), TgaError> {
+        // Convert the pixels before writing anything
+        let mut buf = format!("P6\n{} {}\n255\n", self.header.width, self.header.height).into_bytes();
+        buf.reserve(self.pixel_count() * 3);
+        for y in 0..self.header.height {
+            for x in 0..self.header.width {
+                buf.extend_from_slice(&self.pixel_rgba(x, y)?[..3]);
+            }
+        }
+
+        writer.write_all(&buf).map_err(|e| {FileWrite(e, None)})
+    }
+
+    /// Tries to read a binary (P6) PPM image from `reader` as a 24-bit `TrueColorImage`.
+    /// 
+    /// # Errors
+    /// If the data is not a binary PPM image, returns `InvalidImageType` error.
+    /// 
+    /// If the maximum color value is not 255, returns `InvalidPixelDepth` error.
+    /// 
+    /// If the dimensions are missing or invalid, or the data ends before every pixel is read, returns `InvalidSize` error.
+    /// 
+    /// If the data could not be read, returns `FileRead` error.
+    pub fn from_ppm<R: Read>(reader: &mut R) -> Result<TgaImage, TgaError> {
+        let mut buf = vec![];
+        reader.read_to_end(&mut buf).map_err(|e| {FileRead(e, None)})?;
+
+        // Read the header fields
+        let mut idx = 0;
+        if next_token(&buf, &mut idx) != Some(&b"P6"[..]) {
+            return Err(InvalidImageType);
+        }
+        let width = parse_number(next_token(&buf, &mut idx)).ok_or(InvalidSize)?;
+        let height = parse_number(next_token(&buf, &mut idx)).ok_or(InvalidSize)?;
+        if parse_number(next_token(&buf, &mut idx)) != Some(255) {
+            return Err(InvalidPixelDepth);
+        }
+
+        // A single whitespace character separates the header from the pixels
+        idx += 1;
+        let pixels = buf.get(idx..).ok_or(InvalidSize)?;
+        if pixels.len() < width as usize * height as usize * 3 {
+            return Err(InvalidSize);
+        }
+
+        // Copy each RGB pixel into the image
+        let mut image = TgaImage::new(TrueColorImage, width, height, 24)?;
+        let mut rgb = pixels.chunks_exact(3);
+        for y in 0..height {
+            for x in 0..width {
+                if let Some(&[r, g, b]) = rgb.next() {
+                    image.set_pixel(x, y, TgaColor::RGB24([b, g, r]))?;
+                }
+            }
+        }
+
+        Ok(image)
+    }
+}
+
+/// Returns the next whitespace-separated token in a PPM header, skipping comments.
+fn next_token<'a>(buf: &'a [u8], idx: &mut usize) -> Option<&'a [u8]> {
+    // Skip whitespace and comments
+    while let Some(&c) = buf.get(*idx) {
+        if c == b'#' {
+            while buf.get(*idx).is_some_and(|&c| c != b'\n') {
+                *idx += 1;
+            }
+        } else if c.is_ascii_whitespace() {
+            *idx += 1;
+        } else {
+            break;
+        }
+    }
+
+    let start = *idx;
+    while buf.get(*idx).is_some_and(|c| !c.is_ascii_whitespace()) {
+        *idx += 1;
+    }
+    if start == *idx {
+        return None;
+    }
+
+    Some(&buf[start..*idx])
+}
+
+/// Parses a PPM header token as a number.
+fn parse_number(token: Option<&[u8]>) -> Option<u16> {
+    std::str::from_utf8(token?).ok()?.parse().ok()
+}
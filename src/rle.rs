@@ -0,0 +1,227 @@
+use std::io::{ErrorKind, Read};
+
+use crate::{TgaError, TgaHeader, TgaImage};
+use crate::TgaError::*;
+
+/// Tries to decode the pixel data read from `reader` one scanline at a time.
+/// 
+/// `reader` must be positioned at the start of the pixel data, after the image id and color map. Both run-length encoded and uncompressed image types are supported. `callback` is called with the index and uncompressed bytes of each row, in the order the rows are stored. Only a single row is held in memory at a time.
+/// 
+/// # Errors
+/// If the bit depth is invalid for the image type, returns `InvalidPixelDepth` error.
+/// 
+/// If the data ends before every row is decoded, returns `InvalidSize` error.
+/// 
+/// If the data could not be read, returns `FileRead` error.
+pub fn decode_rows<R: Read, F: FnMut(u16, &[u8])>(reader: &mut R, header: &TgaHeader, mut callback: F) -> Result<(), TgaError> {
+    // Ensure the pixel depth is valid
+    if !header.image_type.valid_depth(header.image_bit_depth) {
+        return Err(InvalidPixelDepth);
+    }
+
+    let byte_depth = (header.image_bit_depth as usize).div_ceil(8);
+    let mut row = vec![0; header.width as usize * byte_depth];
+    let mut decoder = RleDecoder::new(byte_depth);
+    let is_rle = header.image_type.is_rle();
+    for y in 0..header.height {
+        read_row(reader, &mut decoder, is_rle, &mut row)?;
+        callback(y, &row);
+    }
+
+    Ok(())
+}
+
+/// Tries to fill `row` with the next row of pixel data read from `reader`, decoding it with `decoder` if `is_rle` is true.
+/// 
+/// # Errors
+/// If the data ends before the row is filled, returns `InvalidSize` error.
+/// 
+/// If the data could not be read, returns `FileRead` error.
+pub(crate) fn read_row<R: Read>(reader: &mut R, decoder: &mut RleDecoder, is_rle: bool, row: &mut [u8]) -> Result<(), TgaError> {
+    if is_rle {
+        decoder.read_row(reader, row)
+    } else {
+        read_exact(reader, row)
+    }
+}
+
+/// The most bytes of pixel data that a single byte of run-length encoded data can decode to.
+/// 
+/// A run packet of 1 + n bytes decodes to at most 128 pixels of n bytes, so this bounds the size of any pixel data that a buffer could hold.
+const MAX_EXPANSION: usize = 128;
+
+/// Tries to decode the run-length encoded pixel data at the start of `buf`, returning the uncompressed data and the number of bytes decoded.
+/// 
+/// The data is stored in the same row order as `buf`. Bytes after the last packet are ignored.
+/// 
+/// # Errors
+/// Returns the same errors as `decode_rows`.
+pub(crate) fn decode_data(buf: &[u8], header: &TgaHeader) -> Result<(Vec<u8>, usize), TgaError> {
+    // The data grows as rows are decoded, so a truncated buffer fails before the full size is allocated
+    let mut reader = buf;
+    let mut data = vec![];
+    decode_rows(&mut reader, header, |_, row| {data.extend_from_slice(row)})?;
+
+    Ok((data, buf.len() - reader.len()))
+}
+
+/// Tries to decode the pixel data at the start of `buf` like `decode_data`, filling rows that are cut off with zeros.
+/// 
+/// Both run-length encoded and uncompressed image types are supported. If the data is cut off, the whole buffer counts as decoded.
+/// 
+/// # Errors
+/// If the bit depth is invalid for the image type, returns `InvalidPixelDepth` error.
+/// 
+/// If the header's image size is more than 128 times the size of `buf`, returns `InvalidSize` error.
+/// 
+/// If the padded data could not be allocated, returns `AllocationFailed` error.
+pub(crate) fn decode_data_padded(buf: &[u8], header: &TgaHeader) -> Result<(Vec<u8>, usize), TgaError> {
+    // Ensure a tiny buffer cannot declare a huge image to pad
+    let size = header.image_size();
+    if size > buf.len().saturating_mul(MAX_EXPANSION) {
+        return Err(InvalidSize);
+    }
+
+    let mut reader = buf;
+    let mut data = vec![];
+    let consumed = match decode_rows(&mut reader, header, |_, row| {data.extend_from_slice(row)}) {
+        Ok(()) => buf.len() - reader.len(),
+        // The data ran out, so all of it was decoded
+        Err(InvalidSize) => buf.len(),
+        Err(e) => return Err(e)
+    };
+
+    // Ensure the data has every row
+    data.try_reserve_exact(size.saturating_sub(data.len())).map_err(|_| {AllocationFailed})?;
+    data.resize(size, 0);
+
+    Ok((data, consumed))
+}
+
+/// A decoder for run-length encoded pixel data.
+/// 
+/// Packets may span multiple rows, so the state of the current packet is kept between rows.
+pub(crate) struct RleDecoder {
+    byte_depth: usize,
+    remaining: usize,
+    is_run: bool,
+    pixel: [u8; 4],
+}
+
+impl RleDecoder {
+    /// Creates a decoder for pixels of `byte_depth` bytes.
+    pub(crate) fn new(byte_depth: usize) -> RleDecoder {
+        RleDecoder {
+            byte_depth,
+            remaining: 0,
+            is_run: false,
+            pixel: [0; 4]
+        }
+    }
+
+    /// Tries to fill `row` with decoded pixels read from `reader`.
+    pub(crate) fn read_row<R: Read>(&mut self, reader: &mut R, row: &mut [u8]) -> Result<(), TgaError> {
+        let byte_depth = self.byte_depth;
+        for pixel in row.chunks_exact_mut(byte_depth) {
+            // Start a new packet when the current one is finished
+            if self.remaining == 0 {
+                let mut packet_header = [0; 1];
+                read_exact(reader, &mut packet_header)?;
+                self.is_run = packet_header[0] & 0x80 != 0;
+                self.remaining = (packet_header[0] & 0x7f) as usize + 1;
+                if self.is_run {
+                    read_exact(reader, &mut self.pixel[..byte_depth])?;
+                }
+            }
+
+            // Run packets repeat a single pixel while raw packets contain each pixel
+            if self.is_run {
+                pixel.copy_from_slice(&self.pixel[..byte_depth]);
+            } else {
+                read_exact(reader, pixel)?;
+            }
+            self.remaining -= 1;
+        }
+
+        Ok(())
+    }
+}
+
+/// Appends the run-length encoding of `row` to `out`, using pixels of `byte_depth` bytes.
+/// 
+/// Packets never span multiple rows. Runs of two or more identical pixels become run packets, and other pixels are grouped into raw packets.
+pub(crate) fn encode_row(row: &[u8], byte_depth: usize, out: &mut Vec<u8>) {
+    for_each_packet(row, byte_depth, |is_run, pixels| {
+        let count = pixels.len() / byte_depth;
+        if is_run {
+            out.push(0x80 | (count - 1) as u8);
+            out.extend_from_slice(&pixels[..byte_depth]);
+        } else {
+            out.push((count - 1) as u8);
+            out.extend_from_slice(pixels);
+        }
+    });
+}
+
+/// Returns the number of bytes that `encode_row` would append for `row`.
+pub(crate) fn encoded_row_size(row: &[u8], byte_depth: usize) -> usize {
+    let mut size = 0;
+    for_each_packet(row, byte_depth, |is_run, pixels| {
+        size += 1 + if is_run { byte_depth } else { pixels.len() };
+    });
+    size
+}
+
+/// Splits `row` into run-length encoding packets, calling `f` with whether each packet is a run and the pixels it covers.
+fn for_each_packet<F: FnMut(bool, &[u8])>(row: &[u8], byte_depth: usize, mut f: F) {
+    let len = row.len() / byte_depth;
+    let pixel = |i: usize| {&row[i * byte_depth..(i + 1) * byte_depth]};
+    let mut i = 0;
+    while i < len {
+        // Count the identical pixels starting here
+        let mut run = 1;
+        while i + run < len && run < 128 && pixel(i + run) == pixel(i) {
+            run += 1;
+        }
+        if run > 1 {
+            f(true, &row[i * byte_depth..(i + run) * byte_depth]);
+            i += run;
+            continue;
+        }
+
+        // Collect pixels until the next run starts
+        let start = i;
+        while i < len && i - start < 128 && (i + 1 == len || pixel(i + 1) != pixel(i)) {
+            i += 1;
+        }
+        f(false, &row[start * byte_depth..i * byte_depth]);
+    }
+}
+
+impl TgaImage {
+    /// Tries to compute the size in bytes of the pixel data when run-length encoded.
+    /// 
+    /// Rows are encoded the same way as when writing a run-length encoded image type, but nothing is written. Compare the result with `header.image_size()` to decide whether run-length encoding is worth it.
+    /// 
+    /// # Errors
+    /// If the bit depth is invalid for the image type, returns `InvalidPixelDepth` error.
+    pub fn rle_encoded_size(&self) -> Result<usize, TgaError> {
+        let byte_depth = self.byte_depth();
+        if !self.header.image_type.valid_depth(self.header.image_bit_depth) || byte_depth == 0 {
+            return Err(InvalidPixelDepth);
+        }
+
+        Ok(self.rows().map(|row| encoded_row_size(row, byte_depth)).sum())
+    }
+}
+
+/// Tries to fill `buf` from `reader`, treating an early end of data as `InvalidSize`.
+pub(crate) fn read_exact<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<(), TgaError> {
+    reader.read_exact(buf).map_err(|e| {
+        if e.kind() == ErrorKind::UnexpectedEof {
+            InvalidSize
+        } else {
+            FileRead(e, None)
+        }
+    })
+}
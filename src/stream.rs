@@ -0,0 +1,193 @@
+use std::io::{Read, Write};
+
+use crate::{rle, TgaError, TgaHeader, TgaImage};
+use crate::rle::RleDecoder;
+use crate::TgaError::*;
+
+/// A decoder that reads a TGA image from a reader one scanline at a time.
+/// 
+/// The header, image id, and color map are read when the decoder is created, and each call to `next_row` reads and decodes a single row. Only a single row is held in memory at a time, so large images can be converted without buffering their pixel data. The extension and developer areas are not read.
+pub struct TgaScanlineDecoder<R: Read> {
+    reader: R,
+    header: TgaHeader,
+    id: Vec<u8>,
+    color_map: Vec<u8>,
+    decoder: RleDecoder,
+    row: Vec<u8>,
+    rows_read: u16,
+}
+
+impl<R: Read> TgaScanlineDecoder<R> {
+    /// Tries to create a decoder by reading the header, image id, and color map from `reader`.
+    /// 
+    /// # Errors
+    /// If the header is invalid, returns the same errors as `TgaHeader::from_buf`.
+    /// 
+    /// If the header declares a color map without entries, or a color map is declared or required and its bit depth is not 15, 16, 24, or 32, returns `InvalidColorMap` error.
+    /// 
+    /// If the image is color-mapped and its indices are not 8 or 16 bits, returns `UnsupportedIndexSize` error.
+    /// 
+    /// If the bit depth is invalid for the image type, returns `InvalidPixelDepth` error.
+    /// 
+    /// If the data ends before the color map, returns `InvalidSize` error.
+    /// 
+    /// If the data could not be read, returns `FileRead` error.
+    pub fn new(mut reader: R) -> Result<TgaScanlineDecoder<R>, TgaError> {
+        let header = TgaHeader::from_reader(&mut reader)?;
+        header.check_color_map()?;
+        header.check_pixel_depth()?;
+
+        // Read image id and color map
+        let mut id = vec![0; header.id_size as usize];
+        rle::read_exact(&mut reader, &mut id)?;
+        let mut color_map = vec![0; header.color_map_byte_size()];
+        rle::read_exact(&mut reader, &mut color_map)?;
+
+        let byte_depth = (header.image_bit_depth as usize).div_ceil(8);
+        Ok(TgaScanlineDecoder {
+            reader,
+            header,
+            id,
+            color_map,
+            decoder: RleDecoder::new(byte_depth),
+            row: vec![0; header.width as usize * byte_depth],
+            rows_read: 0
+        })
+    }
+
+    /// Returns the header of the image being decoded.
+    pub fn header(&self) -> &TgaHeader {
+        &self.header
+    }
+
+    /// Returns the image id.
+    pub fn id(&self) -> &[u8] {
+        &self.id
+    }
+
+    /// Returns the raw color map data.
+    pub fn color_map(&self) -> &[u8] {
+        &self.color_map
+    }
+
+    /// Tries to read and decode the next row of pixel data.
+    /// 
+    /// Returns the row's index and uncompressed bytes, in the order the rows are stored, or `None` once every row has been read. Run-length encoded rows are decoded.
+    /// 
+    /// # Errors
+    /// If the data ends before the row is decoded, returns `InvalidSize` error.
+    /// 
+    /// If the data could not be read, returns `FileRead` error.
+    pub fn next_row(&mut self) -> Result<Option<(u16, &[u8])>, TgaError> {
+        if self.rows_read == self.header.height {
+            return Ok(None);
+        }
+
+        rle::read_row(&mut self.reader, &mut self.decoder, self.header.image_type.is_rle(), &mut self.row)?;
+        self.rows_read += 1;
+
+        Ok(Some((self.rows_read - 1, &self.row)))
+    }
+}
+
+/// An encoder that writes a TGA image to a writer one scanline at a time.
+/// 
+/// Created by `TgaImage::begin_stream` or `TgaStreamWriter::new`. Only a single row is held in memory at a time, so large images can be generated without buffering their pixel data.
+#[derive(Debug)]
+pub struct TgaStreamWriter<W: Write> {
+    writer: W,
+    header: TgaHeader,
+    rows_written: u16,
+    encoded: Vec<u8>,
+}
+
+/// The encoding counterpart of `TgaScanlineDecoder`.
+pub type TgaScanlineEncoder<W> = TgaStreamWriter<W>;
+
+impl TgaImage {
+    /// Tries to start streaming an image with `header` to `writer`, writing the header immediately.
+    /// 
+    /// Rows are then written with `TgaStreamWriter::write_row`, and the stream is completed with `TgaStreamWriter::finish`. Rows of run-length encoded image types are encoded as they are written.
+    /// 
+    /// # Errors
+    /// If `header` declares an image id or color map, returns `InvalidArgument` error.
+    /// 
+    /// If the bit depth is invalid for the image type, returns `InvalidPixelDepth` error.
+    /// 
+    /// If `header`'s width or height is zero, returns `InvalidSize` error.
+    /// 
+    /// If the header could not be written, returns `FileWrite` error.
+    pub fn begin_stream<W: Write>(mut writer: W, header: TgaHeader) -> Result<TgaStreamWriter<W>, TgaError> {
+        // Ensure the header describes pixel data that can be streamed on its own
+        if header.id_size != 0 || header.has_color_map || header.image_type.is_color_mapped() {
+            return Err(InvalidArgument);
+        }
+        if !header.image_type.valid_depth(header.image_bit_depth) {
+            return Err(InvalidPixelDepth);
+        }
+        if header.width == 0 || header.height == 0 {
+            return Err(InvalidSize);
+        }
+
+        writer.write_all(&header.to_buf()).map_err(|e| {FileWrite(e, None)})?;
+
+        Ok(TgaStreamWriter {
+            writer,
+            header,
+            rows_written: 0,
+            encoded: vec![]
+        })
+    }
+}
+
+impl<W: Write> TgaStreamWriter<W> {
+    /// Tries to start streaming an image with `header` to `writer`, writing the header immediately.
+    /// 
+    /// This is the same as `TgaImage::begin_stream`, and returns the same errors.
+    pub fn new(writer: W, header: TgaHeader) -> Result<TgaStreamWriter<W>, TgaError> {
+        TgaImage::begin_stream(writer, header)
+    }
+
+    /// Tries to write the next row of pixel data.
+    /// 
+    /// Rows are written in the order they are stored, so the first row is the bottom row of the image unless the header's descriptor sets the top origin. `row` must contain uncompressed pixels in storage order.
+    /// 
+    /// # Errors
+    /// If `row` is not exactly one row of pixels long, or every row has already been written, returns `InvalidSize` error.
+    /// 
+    /// If the row could not be written, returns `FileWrite` error.
+    pub fn write_row(&mut self, row: &[u8]) -> Result<(), TgaError> {
+        let byte_depth = (self.header.image_bit_depth as usize).div_ceil(8);
+        if row.len() != self.header.width as usize * byte_depth || self.rows_written == self.header.height {
+            return Err(InvalidSize);
+        }
+
+        if self.header.image_type.is_rle() {
+            self.encoded.clear();
+            rle::encode_row(row, byte_depth, &mut self.encoded);
+            self.writer.write_all(&self.encoded).map_err(|e| {FileWrite(e, None)})?;
+        } else {
+            self.writer.write_all(row).map_err(|e| {FileWrite(e, None)})?;
+        }
+        self.rows_written += 1;
+
+        Ok(())
+    }
+
+    /// Tries to finish the stream, returning the writer.
+    /// 
+    /// The writer is flushed. No extension area or footer is written, so the result is a TGA 1.0 image.
+    /// 
+    /// # Errors
+    /// If fewer rows than the header's height were written, returns `InvalidSize` error.
+    /// 
+    /// If the writer could not be flushed, returns `FileWrite` error.
+    pub fn finish(mut self) -> Result<W, TgaError> {
+        if self.rows_written != self.header.height {
+            return Err(InvalidSize);
+        }
+
+        self.writer.flush().map_err(|e| {FileWrite(e, None)})?;
+        Ok(self.writer)
+    }
+}
@@ -1,4 +1,25 @@
-use crate::{TgaColor, TgaError, TgaImage, TgaImageType};
+use crate::{decode_rows, image_size, DecodeOptions, PixelFormat, ResizeFilter, Strictness, TgaColor, TgaDescriptor, TgaError, TgaExtensionArea, TgaHeader, TgaImage, TgaImageState, TgaImageType, TgaScanlineDecoder, TgaScanlineEncoder, TgaVersion};
+
+use std::path::{Path, PathBuf};
+
+/// Returns the path of `name` in a directory under the system's temporary directory that only this test run uses.
+fn temp_path(name: &str) -> Result<PathBuf, TgaError> {
+    let dir = std::env::temp_dir().join(format!("rtga-tests-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).map_err(|e| {TgaError::FileWrite(e, Some(dir.clone()))})?;
+    Ok(dir.join(name))
+}
+
+/// Returns a directory named `name` under the temporary directory of `temp_path`, creating it if needed.
+fn temp_dir(name: &str) -> Result<PathBuf, TgaError> {
+    let dir = temp_path(name)?;
+    std::fs::create_dir_all(&dir).map_err(|e| {TgaError::FileWrite(e, Some(dir.clone()))})?;
+    Ok(dir)
+}
+
+/// Creates a color-mapped image with 8-bit indices of 0 and the given palette.
+fn indexed_image(width: u16, height: u16, palette: &[TgaColor]) -> Result<TgaImage, TgaError> {
+    TgaImage::new_color_mapped(width, height, palette)
+}
 
 #[test]
 fn write_blank() -> Result<(), TgaError> {
@@ -6,7 +27,7 @@ fn write_blank() -> Result<(), TgaError> {
     let image = TgaImage::new(TgaImageType::TrueColorImage, 1920, 1080, 24)?;
     
     // Write image to file
-    image.to_file("test0.tga")?;
+    image.to_file(temp_path("test0.tga")?)?;
 
     // Create blank image
     let mut image = TgaImage::new(TgaImageType::TrueColorImage, 25, 25, 24)?;
@@ -15,8 +36,2520 @@ fn write_blank() -> Result<(), TgaError> {
     image.set_pixel(0, 0, TgaColor::RGB24([0, 0, 255]))?;
     
     // Write image to file
-    image.to_file("test1.tga")?;
+    image.to_file(temp_path("test1.tga")?)?;
     
     Ok(())
 }
 
+
+#[test]
+fn default_round_trip() -> Result<(), TgaError> {
+    // Create default image
+    let image = TgaImage::default();
+    assert_eq!(image.header.width, 1);
+    assert_eq!(image.header.height, 1);
+    assert_eq!(image.header.image_bit_depth, 24);
+
+    // Serialize and parse image
+    let bytes = image.to_bytes();
+    assert_eq!(bytes.len(), image.header.file_size());
+    let parsed = TgaImage::from_bytes(&bytes)?;
+    assert_eq!(parsed, image);
+
+    Ok(())
+}
+
+#[test]
+fn flood_fill_quadrant() -> Result<(), TgaError> {
+    let red = TgaColor::RGB24([0, 0, 255]);
+    let green = TgaColor::RGB24([0, 255, 0]);
+    let blue = TgaColor::RGB24([255, 0, 0]);
+
+    // Create image with a red top-left quadrant and green elsewhere
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 4, 4, 24)?;
+    for y in 0..4 {
+        for x in 0..4 {
+            image.set_pixel(x, y, if x < 2 && y < 2 { red } else { green })?;
+        }
+    }
+
+    // Fill the red quadrant with blue
+    image.flood_fill(1, 1, blue)?;
+    for y in 0..4 {
+        for x in 0..4 {
+            let expected = if x < 2 && y < 2 { blue } else { green };
+            assert_eq!(image.get_pixel(x, y)?, expected);
+        }
+    }
+
+    // Reject coordinates outside of the image
+    assert!(matches!(image.flood_fill(4, 0, blue), Err(TgaError::InvalidCoordinate)));
+
+    Ok(())
+}
+
+#[test]
+fn data_hash_changes() -> Result<(), TgaError> {
+    // Create two identical images
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 8, 8, 24)?;
+    let copy = image.clone();
+    assert_eq!(image.data_hash(), copy.data_hash());
+
+    // Change a single pixel
+    image.set_pixel(3, 5, TgaColor::RGB24([0, 0, 1]))?;
+    assert_ne!(image.data_hash(), copy.data_hash());
+
+    Ok(())
+}
+
+#[test]
+fn alpha_bits_round_trip() -> Result<(), TgaError> {
+    // Set alpha bits on a 32-bit header with other descriptor bits set
+    let mut header = TgaImage::new(TgaImageType::TrueColorImage, 2, 2, 32)?.header;
+    header.descriptor = 0x20;
+    header.set_alpha_bits(8)?;
+    assert_eq!(header.alpha_bits(), 8);
+    assert_eq!(header.descriptor, 0x28);
+
+    // Round-trip through the header buffer
+    let parsed = TgaHeader::from_buf(header.to_buf())?;
+    assert_eq!(parsed.alpha_bits(), 8);
+
+    // Reject alpha bits that do not fit the bit depth
+    assert!(matches!(header.set_alpha_bits(9), Err(TgaError::InvalidPixelDepth)));
+    header.image_bit_depth = 24;
+    assert!(matches!(header.set_alpha_bits(1), Err(TgaError::InvalidPixelDepth)));
+    header.image_bit_depth = 16;
+    header.set_alpha_bits(1)?;
+    assert_eq!(header.alpha_bits(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn premultiply_round_trip() -> Result<(), TgaError> {
+    // Create image with partially transparent pixels
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 2, 1, 32)?;
+    image.set_pixel(0, 0, TgaColor::RGBA([200, 100, 50, 128]))?;
+    image.set_pixel(1, 0, TgaColor::RGBA([255, 255, 255, 255]))?;
+    let original = image.clone();
+
+    // Premultiply alpha
+    image.premultiply_alpha()?;
+    assert_eq!(image.get_pixel(0, 0)?, TgaColor::RGBA([100, 50, 25, 128]));
+    assert_eq!(image.get_pixel(1, 0)?, TgaColor::RGBA([255, 255, 255, 255]));
+
+    // Unpremultiply alpha and compare, allowing for rounding error
+    image.unpremultiply_alpha()?;
+    for x in 0..2 {
+        let a = image.get_pixel(x, 0)?;
+        let b = original.get_pixel(x, 0)?;
+        for (a, b) in a.as_slice().iter().zip(b.as_slice()) {
+            assert!((*a as i16 - *b as i16).abs() <= 1);
+        }
+    }
+
+    // Reject images without alpha
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 2, 1, 24)?;
+    assert!(matches!(image.premultiply_alpha(), Err(TgaError::InvalidImageType)));
+
+    Ok(())
+}
+
+#[test]
+fn rotate180_swaps_corners() -> Result<(), TgaError> {
+    // Create a gradient image
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 3, 2, 24)?;
+    for y in 0..2 {
+        for x in 0..3 {
+            image.set_pixel(x, y, TgaColor::RGB24([x as u8, y as u8, 100]))?;
+        }
+    }
+    let original = image.clone();
+
+    // Rotate and compare every pixel with its opposite
+    image.rotate180();
+    assert_eq!(image.header.width, 3);
+    assert_eq!(image.header.height, 2);
+    for y in 0..2 {
+        for x in 0..3 {
+            assert_eq!(image.get_pixel(x, y)?, original.get_pixel(2 - x, 1 - y)?);
+        }
+    }
+    assert_eq!(image.get_pixel(0, 0)?, TgaColor::RGB24([2, 1, 100]));
+
+    Ok(())
+}
+
+#[test]
+fn mirror_reverses_rows() -> Result<(), TgaError> {
+    // Create a gradient image
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 3, 2, 32)?;
+    for y in 0..2 {
+        for x in 0..3 {
+            image.set_pixel(x, y, TgaColor::RGBA([x as u8, y as u8, 100, 255]))?;
+        }
+    }
+    let original = image.clone();
+
+    // Mirror and compare every pixel with its horizontal opposite
+    image.mirror();
+    for y in 0..2 {
+        for x in 0..3 {
+            assert_eq!(image.get_pixel(x, y)?, original.get_pixel(2 - x, y)?);
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn copy_region_overlapping() -> Result<(), TgaError> {
+    // Create an image where every pixel is unique
+    let mut image = TgaImage::new(TgaImageType::BlackAndWhiteImage, 4, 4, 8)?;
+    for y in 0..4 {
+        for x in 0..4 {
+            image.set_pixel(x, y, TgaColor::Greyscale([(y * 4 + x) as u8]))?;
+        }
+    }
+    let original = image.clone();
+
+    // Copy a 3x3 region one pixel down and to the right
+    image.copy_region(0, 0, 3, 3, 1, 1)?;
+    for y in 0..4 {
+        for x in 0..4 {
+            let expected = if x >= 1 && y >= 1 {
+                original.get_pixel(x - 1, y - 1)?
+            } else {
+                original.get_pixel(x, y)?
+            };
+            assert_eq!(image.get_pixel(x, y)?, expected);
+        }
+    }
+
+    // Reject rectangles outside of the image
+    assert!(matches!(image.copy_region(0, 0, 2, 2, 3, 3), Err(TgaError::InvalidCoordinate)));
+
+    Ok(())
+}
+
+#[test]
+fn diff_one_pixel() -> Result<(), TgaError> {
+    // Compare an image with itself
+    let image = TgaImage::new(TgaImageType::TrueColorImage, 4, 4, 24)?;
+    let diff = image.diff(&image)?;
+    assert_eq!(diff.differing_pixels, 0);
+    assert_eq!(diff.max_channel_diff, 0);
+    assert!(diff.psnr.is_infinite());
+
+    // Compare with a copy that has one modified pixel
+    let mut modified = image.clone();
+    modified.set_pixel(2, 3, TgaColor::RGB24([10, 0, 40]))?;
+    let diff = image.diff(&modified)?;
+    assert_eq!(diff.differing_pixels, 1);
+    assert_eq!(diff.max_channel_diff, 40);
+    assert!(diff.psnr.is_finite());
+
+    // Reject images with different dimensions
+    let other = TgaImage::new(TgaImageType::TrueColorImage, 4, 5, 24)?;
+    assert!(matches!(image.diff(&other), Err(TgaError::InvalidSize)));
+
+    // Images with different origins are compared as displayed
+    let mut flipped = modified.clone();
+    flipped.set_top_origin(true);
+    assert_ne!(flipped.data(), modified.data());
+    assert_eq!(modified.diff(&flipped)?.differing_pixels, 0);
+    assert_eq!(image.diff(&flipped)?, image.diff(&modified)?);
+
+    Ok(())
+}
+
+#[test]
+fn apply_kernel_sharpen_and_edge() -> Result<(), TgaError> {
+    // Sharpening a flat image leaves it unchanged
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 4, 4, 32)?;
+    for y in 0..4 {
+        for x in 0..4 {
+            image.set_pixel(x, y, TgaColor::RGBA([50, 100, 150, 77]))?;
+        }
+    }
+    let original = image.clone();
+    image.apply_kernel([[0.0, -1.0, 0.0], [-1.0, 5.0, -1.0], [0.0, -1.0, 0.0]], 1.0, 0.0)?;
+    assert_eq!(image, original);
+
+    // Edge detection on a step image only marks the dark side of the step
+    let mut image = TgaImage::new(TgaImageType::BlackAndWhiteImage, 4, 2, 8)?;
+    for y in 0..2 {
+        for x in 2..4 {
+            image.set_pixel(x, y, TgaColor::Greyscale([200]))?;
+        }
+    }
+    image.apply_kernel([[0.0, 1.0, 0.0], [1.0, -4.0, 1.0], [0.0, 1.0, 0.0]], 1.0, 0.0)?;
+    for y in 0..2 {
+        assert_eq!(image.get_pixel(0, y)?, TgaColor::Greyscale([0]));
+        assert_eq!(image.get_pixel(1, y)?, TgaColor::Greyscale([200]));
+        assert_eq!(image.get_pixel(2, y)?, TgaColor::Greyscale([0]));
+        assert_eq!(image.get_pixel(3, y)?, TgaColor::Greyscale([0]));
+    }
+
+    // Reject a zero divisor
+    assert!(matches!(image.apply_kernel([[0.0; 3]; 3], 0.0, 0.0), Err(TgaError::InvalidArgument)));
+
+    Ok(())
+}
+
+#[test]
+fn rgb16_round_trip() -> Result<(), TgaError> {
+    // Create a 24-bit image with arbitrary colors
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 3, 1, 24)?;
+    image.set_pixel(0, 0, TgaColor::RGB24([0, 0, 255]))?;
+    image.set_pixel(1, 0, TgaColor::RGB24([17, 130, 201]))?;
+    image.set_pixel(2, 0, TgaColor::RGB24([255, 255, 255]))?;
+
+    // Convert to 16-bit
+    let packed = image.to_rgb16()?;
+    assert_eq!(packed.header.image_bit_depth, 16);
+    assert_eq!(packed.get_pixel(0, 0)?, TgaColor::RGB16([0x00, 0x7c]));
+
+    // Convert back and compare the top 5 bits of each channel
+    let expanded = packed.rgb16_to_rgb24()?;
+    assert_eq!(expanded.header.image_bit_depth, 24);
+    for x in 0..3 {
+        let a = image.get_pixel(x, 0)?;
+        let b = expanded.get_pixel(x, 0)?;
+        for (a, b) in a.as_slice().iter().zip(b.as_slice()) {
+            assert_eq!(a & 0xf8, b & 0xf8);
+        }
+    }
+    assert_eq!(expanded.get_pixel(2, 0)?, TgaColor::RGB24([255, 255, 255]));
+
+    // Reject images of the wrong bit depth
+    assert!(matches!(image.rgb16_to_rgb24(), Err(TgaError::InvalidImageType)));
+
+    Ok(())
+}
+
+#[test]
+fn tile_wraps_pattern() -> Result<(), TgaError> {
+    // Create a 2x2 tile with a unique value per pixel
+    let mut tile = TgaImage::new(TgaImageType::BlackAndWhiteImage, 2, 2, 8)?;
+    tile.set_pixel(0, 0, TgaColor::Greyscale([1]))?;
+    tile.set_pixel(1, 0, TgaColor::Greyscale([2]))?;
+    tile.set_pixel(0, 1, TgaColor::Greyscale([3]))?;
+    tile.set_pixel(1, 1, TgaColor::Greyscale([4]))?;
+
+    // Tile it over a 5x5 image and check the wrapped pixels
+    let mut image = TgaImage::new(TgaImageType::BlackAndWhiteImage, 5, 5, 8)?;
+    image.tile(&tile)?;
+    for y in 0..5 {
+        for x in 0..5 {
+            assert_eq!(image.get_pixel(x, y)?, tile.get_pixel(x % 2, y % 2)?);
+        }
+    }
+    assert_eq!(image.get_pixel(4, 4)?, TgaColor::Greyscale([1]));
+    assert_eq!(image.get_pixel(3, 4)?, TgaColor::Greyscale([2]));
+
+    // Run-length encoded tiles of the same type are accepted
+    let mut rle_tile = tile.clone();
+    rle_tile.header.image_type = TgaImageType::RleBlackAndWhiteImage;
+    image.tile(&rle_tile)?;
+
+    // Reject tiles with a different image type or bit depth
+    let indexed = indexed_image(2, 2, &[TgaColor::BLACK_RGB24])?;
+    assert!(matches!(image.tile(&indexed), Err(TgaError::InvalidImageType)));
+    let tile = TgaImage::new(TgaImageType::BlackAndWhiteImage, 2, 2, 16)?;
+    assert!(matches!(image.tile(&tile), Err(TgaError::InvalidPixelDepth)));
+
+    // Color-mapped tiles must share the image's color map
+    let mut image = indexed_image(3, 3, &[TgaColor::BLACK_RGB24, TgaColor::WHITE_RGB24])?;
+    let mut tile = image.clone();
+    tile.set_pixel(0, 0, TgaColor::Greyscale([1]))?;
+    image.tile(&tile)?;
+    assert_eq!(image.get_pixel_resolved(0, 0)?, TgaColor::WHITE_RGB24);
+    tile.replace_color(TgaColor::WHITE_RGB24, TgaColor::rgb24(255, 0, 0))?;
+    assert!(matches!(image.tile(&tile), Err(TgaError::InvalidColorMap)));
+
+    Ok(())
+}
+
+#[test]
+fn data_mut_writes_pixels() -> Result<(), TgaError> {
+    // Write a pixel directly through the data buffer
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 2, 2, 24)?;
+    assert_eq!(image.data().len(), 12);
+    let offset = image.byte_offset(1, 0).unwrap();
+    image.data_mut()[offset..offset + 3].copy_from_slice(&[1, 2, 3]);
+
+    // Read it back as a pixel
+    assert_eq!(image.get_pixel(1, 0)?, TgaColor::RGB24([1, 2, 3]));
+
+    Ok(())
+}
+
+#[test]
+fn resize_bilinear_downscale() -> Result<(), TgaError> {
+    // Create a 4x4 horizontal gradient
+    let mut image = TgaImage::new(TgaImageType::BlackAndWhiteImage, 4, 4, 8)?;
+    for y in 0..4 {
+        for x in 0..4 {
+            image.set_pixel(x, y, TgaColor::Greyscale([x as u8 * 40]))?;
+        }
+    }
+
+    // Downscale to 2x2 and check the averaged values
+    let resized = image.resize_bilinear(2, 2)?;
+    assert_eq!(resized.header.width, 2);
+    assert_eq!(resized.header.height, 2);
+    for y in 0..2 {
+        assert_eq!(resized.get_pixel(0, y)?, TgaColor::Greyscale([20]));
+        assert_eq!(resized.get_pixel(1, y)?, TgaColor::Greyscale([100]));
+    }
+
+    // Reject zero dimensions
+    assert!(matches!(image.resize_bilinear(0, 2), Err(TgaError::InvalidSize)));
+
+    Ok(())
+}
+
+#[test]
+fn apply_gamma_lifts_midtones() -> Result<(), TgaError> {
+    // Create image with black, midtone, and white pixels
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 3, 1, 32)?;
+    image.set_pixel(0, 0, TgaColor::RGBA([0, 0, 0, 128]))?;
+    image.set_pixel(1, 0, TgaColor::RGBA([128, 128, 128, 128]))?;
+    image.set_pixel(2, 0, TgaColor::RGBA([255, 255, 255, 128]))?;
+
+    // Apply gamma 2.2, which lifts midtones but keeps the extremes
+    image.apply_gamma(2.2)?;
+    assert_eq!(image.get_pixel(0, 0)?, TgaColor::RGBA([0, 0, 0, 128]));
+    assert_eq!(image.get_pixel(1, 0)?, TgaColor::RGBA([186, 186, 186, 128]));
+    assert_eq!(image.get_pixel(2, 0)?, TgaColor::RGBA([255, 255, 255, 128]));
+
+    // Reject non-positive gamma
+    assert!(matches!(image.apply_gamma(0.0), Err(TgaError::InvalidArgument)));
+
+    Ok(())
+}
+
+#[test]
+fn extract_red_channel() -> Result<(), TgaError> {
+    // Create image with known colors
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 2, 1, 24)?;
+    image.set_pixel(0, 0, TgaColor::RGB24([10, 20, 30]))?;
+    image.set_pixel(1, 0, TgaColor::RGB24([40, 50, 60]))?;
+
+    // Extract the red channel
+    let red = image.extract_channel(2)?;
+    assert_eq!(red.header.image_type, TgaImageType::BlackAndWhiteImage);
+    assert_eq!(red.header.image_bit_depth, 8);
+    assert_eq!(red.get_pixel(0, 0)?, TgaColor::Greyscale([30]));
+    assert_eq!(red.get_pixel(1, 0)?, TgaColor::Greyscale([60]));
+
+    // Reject channels that do not exist
+    assert!(matches!(image.extract_channel(3), Err(TgaError::InvalidColor)));
+
+    Ok(())
+}
+
+#[test]
+fn swap_rb_twice() -> Result<(), TgaError> {
+    // Create image with known colors
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 2, 1, 32)?;
+    image.set_pixel(0, 0, TgaColor::RGBA([10, 20, 30, 40]))?;
+    let original = image.clone();
+
+    // Swap once to reverse red and blue
+    image.swap_rb()?;
+    assert_eq!(image.get_pixel(0, 0)?, TgaColor::RGBA([30, 20, 10, 40]));
+
+    // Swap again to restore the original
+    image.swap_rb()?;
+    assert_eq!(image, original);
+
+    // Reject greyscale images
+    let mut image = TgaImage::new(TgaImageType::BlackAndWhiteImage, 2, 1, 8)?;
+    assert!(matches!(image.swap_rb(), Err(TgaError::InvalidImageType)));
+
+    Ok(())
+}
+
+#[test]
+fn new_rejects_invalid_size() {
+    // Reject images without pixels
+    assert!(matches!(TgaImage::new(TgaImageType::TrueColorImage, 0, 10, 24), Err(TgaError::InvalidSize)));
+    assert!(matches!(TgaImage::new(TgaImageType::TrueColorImage, 10, 0, 24), Err(TgaError::InvalidSize)));
+
+    // The pixel data size is computed without wrapping
+    assert_eq!(image_size(u16::MAX, u16::MAX, 32), (u16::MAX as usize).checked_pow(2).and_then(|n| n.checked_mul(4)));
+}
+
+#[cfg(target_pointer_width = "32")]
+#[test]
+fn new_rejects_overflowing_size() {
+    // The pixel data of this image does not fit in a 32-bit usize
+    assert_eq!(image_size(u16::MAX, u16::MAX, 32), None);
+    assert!(matches!(TgaImage::new(TgaImageType::TrueColorImage, u16::MAX, u16::MAX, 32), Err(TgaError::InvalidSize)));
+}
+
+#[test]
+fn decode_rle_rows() -> Result<(), TgaError> {
+    let mut header = TgaImage::new(TgaImageType::RleBlackAndWhiteImage, 3, 3, 8)?.header;
+
+    // A run within the first row, a raw packet and run in the second row, and a run spanning into the third row
+    let data = [0x82, 5, 0x01, 7, 8, 0x82, 9, 0x00, 4];
+    let mut rows = vec![];
+    decode_rows(&mut &data[..], &header, |y, row| rows.push((y, row.to_vec())))?;
+    assert_eq!(rows, vec![(0, vec![5, 5, 5]), (1, vec![7, 8, 9]), (2, vec![9, 9, 4])]);
+
+    // Reject data that ends early
+    let result = decode_rows(&mut &data[..5], &header, |_, _| {});
+    assert!(matches!(result, Err(TgaError::InvalidSize)));
+
+    // Uncompressed data is read as-is
+    header.image_type = TgaImageType::BlackAndWhiteImage;
+    let mut rows = vec![];
+    decode_rows(&mut &data[..], &header, |_, row| rows.push(row.to_vec()))?;
+    assert_eq!(rows, vec![vec![0x82, 5, 0x01], vec![7, 8, 0x82], vec![9, 0x00, 4]]);
+
+    Ok(())
+}
+
+#[test]
+fn color_from_slice() -> Result<(), TgaError> {
+    // Each valid length creates the matching color format
+    assert_eq!(TgaColor::from_slice(&[1])?, TgaColor::Greyscale([1]));
+    assert_eq!(TgaColor::from_slice(&[1, 2])?, TgaColor::RGB16([1, 2]));
+    assert_eq!(TgaColor::from_slice(&[1, 2, 3])?, TgaColor::RGB24([1, 2, 3]));
+    assert_eq!(TgaColor::from_slice(&[1, 2, 3, 4])?, TgaColor::RGBA([1, 2, 3, 4]));
+    assert_eq!(TgaColor::from_slice(TgaColor::RGB24([4, 5, 6]).as_slice())?, TgaColor::RGB24([4, 5, 6]));
+
+    // Other lengths are rejected
+    assert!(matches!(TgaColor::from_slice(&[]), Err(TgaError::InvalidColor)));
+    assert!(matches!(TgaColor::from_slice(&[1, 2, 3, 4, 5]), Err(TgaError::InvalidColor)));
+
+    Ok(())
+}
+
+#[test]
+fn parse_developer_area() -> Result<(), TgaError> {
+    // Write an image followed by one tag's data
+    let image = TgaImage::default();
+    let mut bytes = image.to_bytes();
+    let data_offset = bytes.len() as u32;
+    bytes.extend_from_slice(b"build-1234");
+
+    // Write the developer directory
+    let developer_offset = bytes.len() as u32;
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&42u16.to_le_bytes());
+    bytes.extend_from_slice(&data_offset.to_le_bytes());
+    bytes.extend_from_slice(&10u32.to_le_bytes());
+
+    // Write the footer
+    bytes.extend_from_slice(&0u32.to_le_bytes());
+    bytes.extend_from_slice(&developer_offset.to_le_bytes());
+    bytes.extend_from_slice(b"TRUEVISION-XFILE.\0");
+
+    // Parse the image and its developer area
+    let parsed = TgaImage::from_bytes(&bytes)?;
+    assert_eq!(parsed.developer_tags().len(), 1);
+    assert_eq!(parsed.developer_tags()[0].id, 42);
+    assert_eq!(parsed.developer_tags()[0].data, b"build-1234");
+    assert_eq!(parsed.data(), image.data());
+
+    // Images without a footer have no tags
+    assert!(TgaImage::from_bytes(&image.to_bytes())?.developer_tags().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn set_top_origin_keeps_image() -> Result<(), TgaError> {
+    // Create image with a unique value per pixel
+    let mut image = TgaImage::new(TgaImageType::BlackAndWhiteImage, 2, 3, 8)?;
+    for y in 0..3 {
+        for x in 0..2 {
+            image.set_pixel(x, y, TgaColor::Greyscale([(y * 2 + x) as u8]))?;
+        }
+    }
+    let original = image.clone();
+
+    // Switch to top-left origin, which reverses the stored rows but keeps every pixel's coordinate
+    image.set_top_origin(true);
+    assert_eq!(image.header.descriptor & 0x20, 0x20);
+    assert_eq!(image.rows().next(), original.rows().last());
+    for y in 0..3 {
+        for x in 0..2 {
+            assert_eq!(image.get_pixel(x, y)?, original.get_pixel(x, y)?);
+        }
+    }
+
+    // Setting the same origin again does nothing
+    let top = image.clone();
+    image.set_top_origin(true);
+    assert_eq!(image, top);
+
+    // Switch back to bottom-left origin
+    image.set_top_origin(false);
+    assert_eq!(image, original);
+
+    Ok(())
+}
+
+#[test]
+fn map_palette_darkens() -> Result<(), TgaError> {
+    // Create indexed image using both palette entries
+    let mut image = indexed_image(2, 2, &[TgaColor::RGB24([100, 150, 200]), TgaColor::RGB24([50, 60, 70])])?;
+    image.set_pixel(1, 1, TgaColor::Greyscale([1]))?;
+    let indices = image.data().to_vec();
+
+    // Halve every palette entry
+    image.map_palette(|color| match color {
+        TgaColor::RGB24(c) => TgaColor::RGB24(c.map(|v| v / 2)),
+        _ => color
+    })?;
+    assert_eq!(image.color_map_entry(0)?, TgaColor::RGB24([50, 75, 100]));
+    assert_eq!(image.color_map_entry(1)?, TgaColor::RGB24([25, 30, 35]));
+    assert_eq!(image.data(), &indices[..]);
+
+    // Reject colors of a different format
+    let result = image.map_palette(|_| TgaColor::RGBA([0, 0, 0, 0]));
+    assert!(matches!(result, Err(TgaError::InvalidPixelDepth)));
+    assert_eq!(image.color_map_entry(0)?, TgaColor::RGB24([50, 75, 100]));
+
+    // Reject greyscale colors with the same byte depth as a 16-bit color map
+    let mut image = indexed_image(1, 1, &[TgaColor::RGB16([1, 2])])?;
+    let result = image.map_palette(|_| TgaColor::GreyscaleAlpha([0, 255]));
+    assert!(matches!(result, Err(TgaError::InvalidPixelDepth)));
+    assert_eq!(image.color_map_entry(0)?, TgaColor::RGB16([1, 2]));
+
+    // Reject images without a color map
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 2, 2, 24)?;
+    assert!(matches!(image.map_palette(|c| c), Err(TgaError::InvalidImageType)));
+
+    Ok(())
+}
+
+#[test]
+fn color_map_round_trip() -> Result<(), TgaError> {
+    // The color map size is a number of entries
+    let image = indexed_image(2, 2, &[TgaColor::RGBA([1, 2, 3, 4]), TgaColor::RGBA([5, 6, 7, 8])])?;
+    assert_eq!(image.header.color_map_byte_size(), 8);
+    assert_eq!(image.header.file_size(), 18 + 8 + 4);
+
+    // Serialize and parse image
+    let parsed = TgaImage::from_bytes(&image.to_bytes())?;
+    assert_eq!(parsed, image);
+    assert_eq!(parsed.color_map_entry(1)?, TgaColor::RGBA([5, 6, 7, 8]));
+    assert!(matches!(parsed.color_map_entry(2), Err(TgaError::InvalidColor)));
+
+    Ok(())
+}
+
+#[test]
+fn rows_cover_image() -> Result<(), TgaError> {
+    // The rows together cover all pixel data
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 5, 3, 24)?;
+    assert_eq!(image.rows().count(), 3);
+    assert_eq!(image.rows().map(|row| row.len()).sum::<usize>(), image.header.image_size());
+
+    // Edit the last stored row, which is the top row of a bottom-left origin image
+    for row in image.rows_mut().skip(2) {
+        row.fill(9);
+    }
+    assert_eq!(image.get_pixel(4, 0)?, TgaColor::RGB24([9, 9, 9]));
+    assert_eq!(image.get_pixel(4, 1)?, TgaColor::RGB24([0, 0, 0]));
+
+    Ok(())
+}
+
+#[test]
+fn resize_canvas_grows() -> Result<(), TgaError> {
+    let red = TgaColor::RGB24([0, 0, 255]);
+    let white = TgaColor::RGB24([255, 255, 255]);
+
+    // Create a 2x2 red image
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 2, 2, 24)?;
+    for y in 0..2 {
+        for x in 0..2 {
+            image.set_pixel(x, y, red)?;
+        }
+    }
+
+    // Grow the canvas and check the copied and filled regions
+    let grown = image.resize_canvas(3, 4, white)?;
+    assert_eq!(grown.header.width, 3);
+    assert_eq!(grown.header.height, 4);
+    for y in 0..4 {
+        for x in 0..3 {
+            let expected = if x < 2 && y < 2 { red } else { white };
+            assert_eq!(grown.get_pixel(x, y)?, expected);
+        }
+    }
+
+    // Shrink the canvas
+    let shrunk = grown.resize_canvas(1, 3, white)?;
+    assert_eq!(shrunk.get_pixel(0, 1)?, red);
+    assert_eq!(shrunk.get_pixel(0, 2)?, white);
+
+    // Reject fill colors of the wrong format
+    assert!(matches!(image.resize_canvas(3, 3, TgaColor::Greyscale([0])), Err(TgaError::InvalidColor)));
+
+    Ok(())
+}
+
+#[test]
+fn pixel_count_and_byte_len() -> Result<(), TgaError> {
+    for (image_type, bit_depth) in [
+        (TgaImageType::BlackAndWhiteImage, 8),
+        (TgaImageType::TrueColorImage, 16),
+        (TgaImageType::TrueColorImage, 24),
+        (TgaImageType::TrueColorImage, 32),
+    ] {
+        let image = TgaImage::new(image_type, 7, 3, bit_depth)?;
+        assert_eq!(image.pixel_count(), 21);
+        assert_eq!(image.byte_len(), 21 * bit_depth as usize / 8);
+        assert_eq!(image.byte_len(), image.header.image_size());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn from_bytes_exact_rejects_trailing_data() -> Result<(), TgaError> {
+    // An exact buffer is accepted
+    let image = TgaImage::new(TgaImageType::TrueColorImage, 3, 2, 24)?;
+    let mut bytes = image.to_bytes();
+    assert_eq!(TgaImage::from_bytes_exact(&bytes)?, image);
+
+    // A footer after the pixel data is accepted
+    let mut footer_bytes = bytes.clone();
+    footer_bytes.extend_from_slice(&[0; 8]);
+    footer_bytes.extend_from_slice(b"TRUEVISION-XFILE.\0");
+    assert_eq!(TgaImage::from_bytes_exact(&footer_bytes)?, image);
+
+    // A single extra byte is rejected, but allowed by the lenient parser
+    bytes.push(0);
+    assert!(matches!(TgaImage::from_bytes_exact(&bytes), Err(TgaError::InvalidSize)));
+    assert_eq!(TgaImage::from_bytes(&bytes)?, image);
+
+    Ok(())
+}
+
+#[test]
+fn image_type_predicates() {
+    use TgaImageType::*;
+
+    // (type, is_rle, is_color_mapped, is_true_color, is_greyscale, base_type)
+    let cases = [
+        (NoImage, false, false, false, false, NoImage),
+        (ColorMappedImage, false, true, false, false, ColorMappedImage),
+        (TrueColorImage, false, false, true, false, TrueColorImage),
+        (BlackAndWhiteImage, false, false, false, true, BlackAndWhiteImage),
+        (RleColorMappedImage, true, true, false, false, ColorMappedImage),
+        (RleTrueColorImage, true, false, true, false, TrueColorImage),
+        (RleBlackAndWhiteImage, true, false, false, true, BlackAndWhiteImage),
+    ];
+    for (image_type, rle, color_mapped, true_color, greyscale, base_type) in cases {
+        assert_eq!(image_type.is_rle(), rle);
+        assert_eq!(image_type.is_color_mapped(), color_mapped);
+        assert_eq!(image_type.is_true_color(), true_color);
+        assert_eq!(image_type.is_greyscale(), greyscale);
+        assert_eq!(image_type.base_type(), base_type);
+    }
+}
+
+#[test]
+fn gamma_round_trip() -> Result<(), TgaError> {
+    // Images have no gamma by default
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 2, 2, 24)?;
+    assert_eq!(image.gamma(), None);
+
+    // Store gamma, which adds an extension area and footer
+    image.set_gamma(2.2);
+    let pixels = image.data().to_vec();
+    let bytes = image.to_bytes();
+    assert_eq!(bytes.len(), image.header.file_size() + 495 + 26);
+    assert!(bytes.ends_with(b"TRUEVISION-XFILE.\0"));
+
+    // Read gamma back from the serialized image
+    let parsed = TgaImage::from_bytes_exact(&bytes)?;
+    assert!((parsed.gamma().unwrap() - 2.2).abs() < 0.001);
+    assert_eq!(parsed.data(), &pixels[..]);
+    assert_eq!(parsed, image);
+
+    Ok(())
+}
+
+#[test]
+fn blend_add_and_sub_saturate() -> Result<(), TgaError> {
+    // Create a grey background and a white overlay
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 3, 3, 32)?;
+    for y in 0..3 {
+        for x in 0..3 {
+            image.set_pixel(x, y, TgaColor::RGBA([128, 128, 128, 100]))?;
+        }
+    }
+    let mut white = TgaImage::new(TgaImageType::TrueColorImage, 2, 2, 32)?;
+    for y in 0..2 {
+        for x in 0..2 {
+            white.set_pixel(x, y, TgaColor::RGBA([255, 255, 255, 255]))?;
+        }
+    }
+
+    // Adding white saturates to white, and the overlay is clipped at the edges
+    let mut added = image.clone();
+    added.blend_add(&white, 2, 2)?;
+    assert_eq!(added.get_pixel(2, 2)?, TgaColor::RGBA([255, 255, 255, 100]));
+    assert_eq!(added.get_pixel(1, 1)?, TgaColor::RGBA([128, 128, 128, 100]));
+
+    // Subtracting white saturates to black
+    let mut subtracted = image.clone();
+    subtracted.blend_sub(&white, 0, 0)?;
+    assert_eq!(subtracted.get_pixel(1, 1)?, TgaColor::RGBA([0, 0, 0, 100]));
+    assert_eq!(subtracted.get_pixel(2, 2)?, TgaColor::RGBA([128, 128, 128, 100]));
+
+    // Reject images of different bit depths
+    let src = TgaImage::new(TgaImageType::TrueColorImage, 1, 1, 24)?;
+    assert!(matches!(image.blend_add(&src, 0, 0), Err(TgaError::InvalidPixelDepth)));
+
+    Ok(())
+}
+
+#[test]
+fn byte_offset_respects_origin() -> Result<(), TgaError> {
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 4, 3, 24)?;
+
+    // In-bounds, boundary, and out-of-bounds coordinates
+    assert!(image.contains(0, 0));
+    assert!(image.contains(3, 2));
+    assert!(!image.contains(4, 2));
+    assert!(!image.contains(3, 3));
+    assert_eq!(image.byte_offset(4, 0), None);
+    assert_eq!(image.byte_offset(0, 3), None);
+
+    // Bottom-left origin stores the top row last
+    assert_eq!(image.byte_offset(0, 0), Some(24));
+    assert_eq!(image.byte_offset(3, 2), Some(9));
+
+    // Top-left origin stores the top row first
+    image.header.descriptor = 0x20;
+    assert_eq!(image.byte_offset(0, 0), Some(0));
+    assert_eq!(image.byte_offset(3, 2), Some(33));
+
+    // Top-right origin stores each row from right to left
+    image.header.descriptor = 0x30;
+    assert_eq!(image.byte_offset(0, 0), Some(9));
+
+    // Pixels are written at the origin-aware offset
+    image.set_pixel(0, 0, TgaColor::RGB24([1, 2, 3]))?;
+    assert_eq!(&image.data()[9..12], &[1, 2, 3]);
+
+    Ok(())
+}
+
+#[test]
+fn write_ppm() -> Result<(), TgaError> {
+    // Create image with a red top-left pixel and a blue bottom-right pixel
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 2, 2, 24)?;
+    image.set_pixel(0, 0, TgaColor::RGB24([0, 0, 255]))?;
+    image.set_pixel(1, 1, TgaColor::RGB24([255, 0, 0]))?;
+
+    // Write the image as a PPM and check the header and RGB pixels
+    let mut ppm = vec![];
+    image.to_ppm(&mut ppm)?;
+    let header = b"P6\n2 2\n255\n";
+    assert!(ppm.starts_with(header));
+    assert_eq!(ppm.len(), header.len() + 12);
+    assert_eq!(&ppm[header.len()..header.len() + 3], &[255, 0, 0]);
+    assert_eq!(&ppm[ppm.len() - 3..], &[0, 0, 255]);
+
+    // Color-mapped pixels are written as their color map entry
+    let mut image = indexed_image(1, 1, &[TgaColor::RGB24([0, 0, 0]), TgaColor::RGB24([30, 20, 10])])?;
+    image.set_pixel(0, 0, TgaColor::Greyscale([1]))?;
+    let mut ppm = vec![];
+    image.to_ppm(&mut ppm)?;
+    assert_eq!(&ppm[ppm.len() - 3..], &[10, 20, 30]);
+
+    Ok(())
+}
+
+#[test]
+fn ppm_round_trip() -> Result<(), TgaError> {
+    // Create image with a unique color per pixel
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 3, 2, 24)?;
+    for y in 0..2 {
+        for x in 0..3 {
+            image.set_pixel(x, y, TgaColor::RGB24([x as u8, y as u8, 200]))?;
+        }
+    }
+
+    // Export to PPM and import it back
+    let mut ppm = vec![];
+    image.to_ppm(&mut ppm)?;
+    assert_eq!(TgaImage::from_ppm(&mut &ppm[..])?, image);
+
+    // Comments in the header are skipped
+    let ppm = b"P6\n# comment\n1 1\n255\n\x01\x02\x03";
+    assert_eq!(TgaImage::from_ppm(&mut &ppm[..])?.get_pixel(0, 0)?, TgaColor::RGB24([3, 2, 1]));
+
+    // Reject other PPM subtypes and color depths
+    assert!(matches!(TgaImage::from_ppm(&mut &b"P3\n1 1\n255\n0 0 0"[..]), Err(TgaError::InvalidImageType)));
+    assert!(matches!(TgaImage::from_ppm(&mut &b"P6\n1 1\n65535\n\0\0\0\0\0\0"[..]), Err(TgaError::InvalidPixelDepth)));
+
+    Ok(())
+}
+
+#[test]
+fn verify_buffer_sizes() -> Result<(), TgaError> {
+    // A consistent image passes
+    let image = indexed_image(2, 2, &[TgaColor::RGB24([1, 2, 3])])?;
+    image.verify()?;
+
+    // An id of the wrong length fails
+    let mut broken = image.clone();
+    broken.header.id_size = 1;
+    assert!(matches!(broken.verify(), Err(TgaError::InvalidSize)));
+
+    // A color map of the wrong length fails
+    let mut broken = image.clone();
+    broken.header.color_map_size = 2;
+    assert!(matches!(broken.verify(), Err(TgaError::InvalidColorMap)));
+
+    // Pixel data of the wrong length fails
+    let mut broken = image.clone();
+    broken.data = vec![0; 3].into_boxed_slice();
+    assert!(matches!(broken.verify(), Err(TgaError::InvalidSize)));
+
+    Ok(())
+}
+
+#[test]
+fn compact_color_map_removes_unused() -> Result<(), TgaError> {
+    let palette = [
+        TgaColor::RGB24([1, 1, 1]),
+        TgaColor::RGB24([2, 2, 2]),
+        TgaColor::RGB24([3, 3, 3]),
+        TgaColor::RGB24([4, 4, 4]),
+    ];
+
+    // Create indexed image that only uses entries 1 and 3
+    let mut image = indexed_image(2, 2, &palette)?;
+    image.set_pixel(0, 0, TgaColor::Greyscale([1]))?;
+    image.set_pixel(1, 0, TgaColor::Greyscale([3]))?;
+    image.set_pixel(0, 1, TgaColor::Greyscale([3]))?;
+    image.set_pixel(1, 1, TgaColor::Greyscale([1]))?;
+    let resolved = image.resolve_color_map()?;
+    assert_eq!(resolved.header.image_type, TgaImageType::TrueColorImage);
+    assert_eq!(resolved.get_pixel(1, 0)?, TgaColor::RGB24([4, 4, 4]));
+
+    // Compact the color map
+    image.compact_color_map()?;
+    assert_eq!(image.header.color_map_size, 2);
+    assert_eq!(image.color_map_entry(0)?, TgaColor::RGB24([2, 2, 2]));
+    assert_eq!(image.color_map_entry(1)?, TgaColor::RGB24([4, 4, 4]));
+    image.verify()?;
+
+    // The resolved pixels are unchanged
+    assert_eq!(image.resolve_color_map()?, resolved);
+
+    Ok(())
+}
+
+#[test]
+fn rgba_vec_expands_channels() -> Result<(), TgaError> {
+    // Greyscale pixels are replicated and opaque
+    let mut image = TgaImage::new(TgaImageType::BlackAndWhiteImage, 2, 1, 8)?;
+    image.set_pixel(1, 0, TgaColor::Greyscale([77]))?;
+    assert_eq!(image.as_rgba_vec()?, vec![0, 0, 0, 255, 77, 77, 77, 255]);
+
+    // 24-bit pixels are swapped to RGB order and opaque
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 1, 2, 24)?;
+    image.set_pixel(0, 0, TgaColor::RGB24([1, 2, 3]))?;
+    image.set_pixel(0, 1, TgaColor::RGB24([4, 5, 6]))?;
+    assert_eq!(image.as_rgba_vec()?, vec![3, 2, 1, 255, 6, 5, 4, 255]);
+
+    // 32-bit pixels keep their alpha
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 1, 1, 32)?;
+    image.set_pixel(0, 0, TgaColor::RGBA([1, 2, 3, 4]))?;
+    assert_eq!(image.as_rgba_vec()?, vec![3, 2, 1, 4]);
+
+    // 16-bit pixels use the attribute bit as alpha if the descriptor declares it
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 1, 1, 16)?;
+    image.set_pixel(0, 0, TgaColor::RGB16([0x1f, 0x00]))?;
+    assert_eq!(image.as_rgba_vec()?, vec![0, 0, 255, 255]);
+    image.header.set_alpha_bits(1)?;
+    assert_eq!(image.as_rgba_vec()?, vec![0, 0, 255, 0]);
+
+    Ok(())
+}
+
+#[test]
+fn crop_to_content_trims_border() -> Result<(), TgaError> {
+    let border = TgaColor::RGB24([255, 0, 255]);
+    let content = TgaColor::RGB24([1, 2, 3]);
+
+    // Create a 6x5 image with a 2x3 square of content surrounded by a border
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 6, 5, 24)?;
+    for y in 0..5 {
+        for x in 0..6 {
+            let inside = (2..4).contains(&x) && (1..4).contains(&y);
+            image.set_pixel(x, y, if inside { content } else { border })?;
+        }
+    }
+    image.set_pixel(2, 1, TgaColor::RGB24([9, 9, 9]))?;
+
+    // Trim the border
+    let trimmed = image.crop_to_content(border)?;
+    assert_eq!(trimmed.header.width, 2);
+    assert_eq!(trimmed.header.height, 3);
+    assert_eq!(trimmed.get_pixel(0, 0)?, TgaColor::RGB24([9, 9, 9]));
+    assert_eq!(trimmed.get_pixel(1, 2)?, content);
+
+    // An image containing only the border color cannot be trimmed
+    let blank = image.crop(0, 0, 2, 1)?;
+    assert!(matches!(blank.crop_to_content(border), Err(TgaError::InvalidSize)));
+
+    Ok(())
+}
+
+#[test]
+fn color_mapped_palette_depths() -> Result<(), TgaError> {
+    // 24-bit palette entries are read as RGB24 colors
+    let mut image = indexed_image(2, 1, &[TgaColor::RGB24([1, 2, 3]), TgaColor::RGB24([4, 5, 6])])?;
+    image.set_pixel(1, 0, TgaColor::Greyscale([1]))?;
+    let parsed = TgaImage::from_bytes(&image.to_bytes())?;
+    assert_eq!(parsed.color_map_entry(1)?, TgaColor::RGB24([4, 5, 6]));
+    let resolved = parsed.resolve_color_map()?;
+    assert_eq!(resolved.header.image_bit_depth, 24);
+    assert_eq!(resolved.get_pixel(0, 0)?, TgaColor::RGB24([1, 2, 3]));
+    assert_eq!(resolved.get_pixel(1, 0)?, TgaColor::RGB24([4, 5, 6]));
+
+    // 32-bit palette entries are read as RGBA colors
+    let mut image = indexed_image(2, 1, &[TgaColor::RGBA([1, 2, 3, 0]), TgaColor::RGBA([4, 5, 6, 255])])?;
+    image.set_pixel(1, 0, TgaColor::Greyscale([1]))?;
+    let parsed = TgaImage::from_bytes(&image.to_bytes())?;
+    assert_eq!(parsed.color_map_entry(0)?, TgaColor::RGBA([1, 2, 3, 0]));
+    let resolved = parsed.resolve_color_map()?;
+    assert_eq!(resolved.header.image_bit_depth, 32);
+    assert_eq!(resolved.header.alpha_bits(), 8);
+    assert_eq!(resolved.get_pixel(1, 0)?, TgaColor::RGBA([4, 5, 6, 255]));
+
+    // 15-bit palette entries are read as opaque RGB16 colors
+    let mut image = indexed_image(2, 1, &[TgaColor::RGB16([0x1f, 0x00]), TgaColor::RGB16([0xe0, 0x83])])?;
+    image.header.color_map_bit_depth = 15;
+    image.set_pixel(1, 0, TgaColor::Greyscale([1]))?;
+    let parsed = TgaImage::from_bytes(&image.to_bytes())?;
+    assert_eq!(parsed.color_map_entry(0)?, TgaColor::RGB16([0x1f, 0x80]));
+    assert_eq!(parsed.get_pixel_resolved(1, 0)?, TgaColor::RGB16([0xe0, 0x83]));
+    assert_eq!(parsed.get_pixel_resolved(0, 0)?.to_rgba8(), [0, 0, 255, 255]);
+    assert_eq!(parsed.resolve_color_map()?.header.image_bit_depth, 15);
+
+    // Unsupported palette depths are rejected when loading
+    let mut image = indexed_image(1, 1, &[TgaColor::BLACK_RGB24])?;
+    image.header.color_map_bit_depth = 8;
+    assert!(matches!(TgaImage::from_bytes(&image.to_bytes()), Err(TgaError::InvalidColorMap)));
+
+    Ok(())
+}
+
+#[test]
+fn adjust_saturation_to_grey() -> Result<(), TgaError> {
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 3, 2, 32)?;
+    let colors = [[0, 0, 255, 10], [0, 255, 0, 20], [255, 0, 0, 30], [40, 200, 90, 40], [10, 20, 30, 50], [128, 128, 128, 60]];
+    for (i, color) in colors.iter().enumerate() {
+        image.set_pixel(i as u16 % 3, i as u16 / 3, TgaColor::RGBA(*color))?;
+    }
+
+    // A factor of 1 leaves the colors unchanged
+    let original = image.clone();
+    image.adjust_saturation(1.0)?;
+    assert_eq!(image, original);
+
+    // A factor of 0 makes every pixel grey and keeps the alpha channel
+    image.adjust_saturation(0.0)?;
+    for (i, color) in colors.iter().enumerate() {
+        let pixel = image.get_pixel(i as u16 % 3, i as u16 / 3)?;
+        let [b, g, r, a] = <[u8; 4]>::try_from(pixel.as_slice()).unwrap();
+        assert!(r == g && g == b);
+        assert_eq!(a, color[3]);
+    }
+
+    // Only true color images can be adjusted
+    let mut grey = TgaImage::new(TgaImageType::BlackAndWhiteImage, 1, 1, 8)?;
+    assert!(matches!(grey.adjust_saturation(0.0), Err(TgaError::InvalidImageType)));
+    assert!(matches!(image.adjust_saturation(-1.0), Err(TgaError::InvalidArgument)));
+
+    Ok(())
+}
+
+#[test]
+fn from_file_versioned_detects_footer() -> Result<(), TgaError> {
+    // Images without an extension area are written without a footer
+    let image = TgaImage::new(TgaImageType::TrueColorImage, 2, 2, 24)?;
+    let v1_path = temp_path("test_v1.tga")?;
+    image.to_file(&v1_path)?;
+    let (parsed, version) = TgaImage::from_file_versioned(&v1_path)?;
+    assert_eq!(parsed, image);
+    assert_eq!(version, TgaVersion::V1);
+
+    // Images with an extension area end with a footer
+    let mut image = image;
+    image.set_gamma(2.2);
+    let v2_path = temp_path("test_v2.tga")?;
+    image.to_file(&v2_path)?;
+    let (parsed, version) = TgaImage::from_file_versioned(&v2_path)?;
+    assert_eq!(parsed, image);
+    assert_eq!(version, TgaVersion::V2);
+
+    Ok(())
+}
+
+#[test]
+fn pixels_in_rect_window() -> Result<(), TgaError> {
+    let mut image = TgaImage::new(TgaImageType::BlackAndWhiteImage, 4, 4, 8)?;
+    for y in 0..4 {
+        for x in 0..4 {
+            image.set_pixel(x, y, TgaColor::Greyscale([(y * 4 + x) as u8]))?;
+        }
+    }
+
+    // Iterate the 2x2 window in the center of the image
+    let pixels: Vec<_> = image.pixels_in_rect(1, 1, 2, 2)?.collect();
+    assert_eq!(pixels, vec![
+        (1, 1, TgaColor::Greyscale([5])),
+        (2, 1, TgaColor::Greyscale([6])),
+        (1, 2, TgaColor::Greyscale([9])),
+        (2, 2, TgaColor::Greyscale([10])),
+    ]);
+
+    // Rectangles outside of the image are rejected
+    assert!(matches!(image.pixels_in_rect(3, 3, 2, 1), Err(TgaError::InvalidCoordinate)));
+
+    Ok(())
+}
+
+#[test]
+fn from_channels_merges_planes() -> Result<(), TgaError> {
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 3, 2, 32)?;
+    image.header.set_alpha_bits(8)?;
+    for (i, pixel) in image.data_mut().iter_mut().enumerate() {
+        *pixel = (i * 7) as u8;
+    }
+
+    // Split into channels and merge them back together
+    let b = image.extract_channel(0)?;
+    let g = image.extract_channel(1)?;
+    let r = image.extract_channel(2)?;
+    let a = image.extract_channel(3)?;
+    assert_eq!(TgaImage::from_channels(&r, &g, &b, Some(&a))?, image);
+
+    // Without alpha the merged image is 24-bit
+    let merged = TgaImage::from_channels(&r, &g, &b, None)?;
+    assert_eq!(merged.header.image_bit_depth, 24);
+    assert_eq!(merged.get_pixel(0, 0)?.as_slice(), &image.get_pixel(0, 0)?.as_slice()[0..3]);
+
+    // Mismatched dimensions are rejected
+    let small = TgaImage::new(TgaImageType::BlackAndWhiteImage, 1, 1, 8)?;
+    assert!(matches!(TgaImage::from_channels(&r, &g, &small, None), Err(TgaError::InvalidSize)));
+    assert!(matches!(TgaImage::from_channels(&r, &g, &image, None), Err(TgaError::InvalidImageType)));
+
+    Ok(())
+}
+
+#[test]
+fn thumbnail_preserves_aspect_ratio() -> Result<(), TgaError> {
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 100, 50, 24)?;
+    image.set_pixel(99, 49, TgaColor::RGB24([0, 0, 255]))?;
+
+    // The longest side becomes max_dim
+    let thumbnail = image.thumbnail(10)?;
+    assert_eq!((thumbnail.header.width, thumbnail.header.height), (10, 5));
+
+    // The shorter side never becomes zero
+    let thumbnail = image.thumbnail(1)?;
+    assert_eq!((thumbnail.header.width, thumbnail.header.height), (1, 1));
+    assert!(matches!(image.thumbnail(0), Err(TgaError::InvalidSize)));
+
+    // Nearest-neighbor sampling keeps exact pixel values
+    let resized = image.resize_nearest(50, 25)?;
+    assert_eq!(resized.get_pixel(49, 24)?, TgaColor::RGB24([0, 0, 255]));
+    assert_eq!(resized.get_pixel(0, 0)?, TgaColor::RGB24([0, 0, 0]));
+
+    Ok(())
+}
+
+#[test]
+fn header_buf_round_trip() -> Result<(), TgaError> {
+    // Use values that need both bytes of every multi-byte field
+    let header = TgaHeader {
+        id_size: 3,
+        has_color_map: true,
+        image_type: TgaImageType::ColorMappedImage,
+        color_map_first_index: 0x0102,
+        color_map_size: 0x0304,
+        color_map_bit_depth: 24,
+        x_origin: 0x0506,
+        y_origin: 0x0708,
+        width: 0x090a,
+        height: 0xfffe,
+        image_bit_depth: 8,
+        descriptor: 0x20
+    };
+    let buf = header.to_buf();
+    assert_eq!(&buf[3..7], &[0x02, 0x01, 0x04, 0x03]);
+    assert_eq!(&buf[12..16], &[0x0a, 0x09, 0xfe, 0xff]);
+    assert_eq!(TgaHeader::from_buf(buf)?, header);
+
+    Ok(())
+}
+
+#[test]
+fn replace_color_counts_pixels() -> Result<(), TgaError> {
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 3, 2, 24)?;
+    image.set_pixel(0, 0, TgaColor::RGB24([0, 255, 0]))?;
+    image.set_pixel(2, 1, TgaColor::RGB24([0, 255, 0]))?;
+
+    // Replace the green pixels with red
+    assert_eq!(image.replace_color(TgaColor::RGB24([0, 255, 0]), TgaColor::RGB24([0, 0, 255]))?, 2);
+    assert_eq!(image.get_pixel(2, 1)?, TgaColor::RGB24([0, 0, 255]));
+    assert_eq!(image.get_pixel(1, 0)?, TgaColor::RGB24([0, 0, 0]));
+    assert!(matches!(image.replace_color(TgaColor::Greyscale([0]), TgaColor::RGB24([0, 0, 0])), Err(TgaError::InvalidColor)));
+
+    // Color-mapped images replace color map entries
+    let mut image = indexed_image(3, 1, &[TgaColor::RGB24([1, 1, 1]), TgaColor::RGB24([2, 2, 2])])?;
+    image.set_pixel(2, 0, TgaColor::Greyscale([1]))?;
+    assert_eq!(image.replace_color(TgaColor::RGB24([1, 1, 1]), TgaColor::RGB24([3, 3, 3]))?, 2);
+    assert_eq!(image.color_map_entry(0)?, TgaColor::RGB24([3, 3, 3]));
+    assert_eq!(image.get_pixel(0, 0)?, TgaColor::Greyscale([0]));
+
+    // Greyscale colors are not color map entries, even with a matching byte depth
+    let mut image = indexed_image(1, 1, &[TgaColor::RGB16([1, 2])])?;
+    assert!(matches!(image.replace_color(TgaColor::GreyscaleAlpha([1, 2]), TgaColor::RGB16([3, 4])), Err(TgaError::InvalidColor)));
+    assert_eq!(image.color_map_entry(0)?, TgaColor::RGB16([1, 2]));
+
+    Ok(())
+}
+
+#[test]
+fn set_pixel_unchecked_writes_pixels() -> Result<(), TgaError> {
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 4, 4, 24)?;
+    let mut checked = image.clone();
+
+    // Unchecked writes match checked writes for valid input
+    for y in 0..4 {
+        for x in 0..4 {
+            let color = TgaColor::RGB24([x as u8, y as u8, 7]);
+            unsafe { image.set_pixel_unchecked(x, y, color) };
+            checked.set_pixel(x, y, color)?;
+        }
+    }
+    assert_eq!(image, checked);
+
+    Ok(())
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "pixel coordinate outside of image")]
+fn set_pixel_unchecked_debug_asserts() {
+    let mut image = TgaImage::default();
+    unsafe { image.set_pixel_unchecked(1, 0, TgaColor::RGB24([0, 0, 0])) };
+}
+
+#[test]
+fn load_15_bit_image() -> Result<(), TgaError> {
+    // Build a 2x1 15-bit file by hand
+    let header = TgaHeader { width: 2, image_bit_depth: 15, ..TgaHeader::default() };
+    let mut buf = header.to_buf().to_vec();
+    buf.extend_from_slice(&[0x1f, 0x00, 0x00, 0xfc]);
+
+    // Each pixel uses two bytes
+    let mut image = TgaImage::from_bytes(&buf)?;
+    assert_eq!(image.data().len(), 4);
+    assert_eq!(image.get_pixel(0, 0)?, TgaColor::RGB16([0x1f, 0x00]));
+    assert_eq!(image.get_pixel(1, 0)?, TgaColor::RGB16([0x00, 0xfc]));
+
+    // The top bit is ignored when expanding pixels
+    let expanded = image.rgb16_to_rgb24()?;
+    assert_eq!(expanded.get_pixel(0, 0)?, TgaColor::RGB24([255, 0, 0]));
+    assert_eq!(expanded.get_pixel(1, 0)?, TgaColor::RGB24([0, 0, 255]));
+
+    // Pixels can be written as 16-bit colors
+    image.set_pixel(0, 0, TgaColor::RGB16([0xe0, 0x03]))?;
+    assert_eq!(TgaImage::from_bytes(&image.to_bytes())?, image);
+
+    Ok(())
+}
+
+#[test]
+fn image_size_rounds_up_bit_depth() {
+    // Partial bytes are rounded up to whole bytes per pixel
+    assert_eq!(image_size(10, 10, 15), Some(200));
+    assert_eq!(image_size(10, 10, 16), Some(200));
+    assert_eq!(image_size(10, 10, 1), Some(100));
+    assert_eq!(image_size(10, 10, 0), Some(0));
+}
+
+#[test]
+fn has_at_most_colors_limit() -> Result<(), TgaError> {
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 4, 4, 24)?;
+    image.set_pixel(0, 0, TgaColor::RGB24([1, 0, 0]))?;
+    image.set_pixel(1, 0, TgaColor::RGB24([2, 0, 0]))?;
+    image.set_pixel(2, 3, TgaColor::RGB24([2, 0, 0]))?;
+
+    // Three colors including black
+    assert!(image.has_at_most_colors(3)?);
+    assert!(!image.has_at_most_colors(2)?);
+
+    // One more color exceeds the limit
+    image.set_pixel(3, 3, TgaColor::RGB24([3, 0, 0]))?;
+    assert!(!image.has_at_most_colors(3)?);
+    assert!(image.has_at_most_colors(4)?);
+
+    Ok(())
+}
+
+#[test]
+fn new_with_color_fills_pixels() -> Result<(), TgaError> {
+    // Create a solid white image
+    let white = TgaColor::RGB24([255, 255, 255]);
+    let image = TgaImage::new_with_color(TgaImageType::TrueColorImage, 5, 3, 24, white)?;
+    assert_eq!(image.get_pixel(0, 0)?, white);
+    assert_eq!(image.get_pixel(4, 2)?, white);
+    assert!(image.data().iter().all(|&byte| byte == 255));
+
+    // The color must match the image format
+    assert!(matches!(TgaImage::new_with_color(TgaImageType::TrueColorImage, 5, 3, 32, white), Err(TgaError::InvalidPixelDepth)));
+    assert!(matches!(TgaImage::new_with_color(TgaImageType::BlackAndWhiteImage, 5, 3, 8, white), Err(TgaError::InvalidColor)));
+
+    Ok(())
+}
+
+#[test]
+fn rle_top_origin_round_trip() -> Result<(), TgaError> {
+    let mut image = TgaImage::new(TgaImageType::RleTrueColorImage, 5, 3, 24)?;
+    image.set_top_origin(true);
+    image.set_pixel(0, 0, TgaColor::RGB24([0, 0, 255]))?;
+    image.set_pixel(1, 0, TgaColor::RGB24([0, 255, 0]))?;
+    image.set_pixel(4, 2, TgaColor::RGB24([255, 0, 0]))?;
+
+    // The descriptor is written unchanged
+    let buf = image.to_bytes();
+    let header = TgaHeader::from_buf(buf[..18].try_into().unwrap())?;
+    assert_eq!(header, image.header);
+    assert!(buf.len() < 18 + image.data().len());
+
+    // The decoded image matches the original
+    assert_eq!(TgaImage::from_bytes(&buf)?, image);
+
+    Ok(())
+}
+
+#[test]
+fn color_to_rgba8() {
+    assert_eq!(TgaColor::Greyscale([7]).to_rgba8(), [7, 7, 7, 255]);
+    assert_eq!(TgaColor::RGB24([1, 2, 3]).to_rgba8(), [3, 2, 1, 255]);
+    assert_eq!(TgaColor::RGBA([1, 2, 3, 4]).to_rgba8(), [3, 2, 1, 4]);
+
+    // 16-bit channels are expanded and the attribute bit becomes alpha
+    assert_eq!(TgaColor::RGB16([0x1f, 0x80]).to_rgba8(), [0, 0, 255, 255]);
+    assert_eq!(TgaColor::RGB16([0x00, 0x7c]).to_rgba8(), [255, 0, 0, 0]);
+}
+
+#[test]
+fn draw_rect_outline() -> Result<(), TgaError> {
+    let mut image = TgaImage::new(TgaImageType::BlackAndWhiteImage, 6, 5, 8)?;
+    let white = TgaColor::Greyscale([255]);
+    image.draw_rect(1, 1, 4, 3, white)?;
+
+    // Only the edges of the rectangle are set
+    for y in 0..5 {
+        for x in 0..6 {
+            let on_edge = (1..=4).contains(&x) && (1..=3).contains(&y) && (x == 1 || x == 4 || y == 1 || y == 3);
+            let expected = if on_edge { 255 } else { 0 };
+            assert_eq!(image.get_pixel(x, y)?, TgaColor::Greyscale([expected]));
+        }
+    }
+
+    // Zero-size rectangles draw nothing, and rectangles outside of the image are rejected
+    let copy = image.clone();
+    image.draw_rect(0, 0, 0, 5, white)?;
+    assert_eq!(image, copy);
+    assert!(matches!(image.draw_rect(3, 3, 4, 1, white), Err(TgaError::InvalidCoordinate)));
+
+    Ok(())
+}
+
+#[test]
+fn downscale_average_midtones() -> Result<(), TgaError> {
+    // Alternate black and white columns
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 4, 4, 24)?;
+    for y in 0..4 {
+        for x in (0..4).step_by(2) {
+            image.set_pixel(x, y, TgaColor::RGB24([255, 255, 255]))?;
+        }
+    }
+
+    // Every 2x2 region averages to a midtone
+    let small = image.downscale_average(2, 2)?;
+    assert_eq!((small.header.width, small.header.height), (2, 2));
+    for y in 0..2 {
+        for x in 0..2 {
+            assert_eq!(small.get_pixel(x, y)?, TgaColor::RGB24([128, 128, 128]));
+        }
+    }
+
+    // Upscaling and color-mapped images are rejected
+    assert!(matches!(image.downscale_average(8, 2), Err(TgaError::InvalidSize)));
+    let indexed = indexed_image(2, 2, &[TgaColor::RGB24([0, 0, 0])])?;
+    assert!(matches!(indexed.downscale_average(1, 1), Err(TgaError::InvalidImageType)));
+
+    Ok(())
+}
+
+#[test]
+fn color_mapped_16_bit_indices() -> Result<(), TgaError> {
+    // Build a 2x1 image with 16-bit indices into a palette starting at index 256
+    let header = TgaHeader {
+        has_color_map: true,
+        image_type: TgaImageType::ColorMappedImage,
+        color_map_first_index: 256,
+        color_map_size: 2,
+        color_map_bit_depth: 24,
+        width: 2,
+        image_bit_depth: 16,
+        ..TgaHeader::default()
+    };
+    let mut buf = header.to_buf().to_vec();
+    buf.extend_from_slice(&[1, 2, 3, 4, 5, 6]);
+    buf.extend_from_slice(&[0x01, 0x01, 0x00, 0x01]);
+
+    // Indices are read as little-endian 16-bit values
+    let mut image = TgaImage::from_bytes(&buf)?;
+    assert_eq!(image.get_pixel(0, 0)?, TgaColor::RGB16([0x01, 0x01]));
+    let resolved = image.resolve_color_map()?;
+    assert_eq!(resolved.get_pixel(0, 0)?, TgaColor::RGB24([4, 5, 6]));
+    assert_eq!(resolved.get_pixel(1, 0)?, TgaColor::RGB24([1, 2, 3]));
+    assert_eq!(image.as_rgba_vec()?, vec![6, 5, 4, 255, 3, 2, 1, 255]);
+
+    // Compacting rewrites both bytes of each index
+    image.set_pixel(1, 0, TgaColor::RGB16([0x01, 0x01]))?;
+    image.compact_color_map()?;
+    assert_eq!(image.data(), &[0, 0, 0, 0]);
+    assert_eq!(image.color_map_entry(0)?, TgaColor::RGB24([4, 5, 6]));
+
+    // Other index sizes are rejected
+    assert!(matches!(TgaImage::new(TgaImageType::ColorMappedImage, 1, 1, 24), Err(TgaError::UnsupportedIndexSize)));
+    buf[16] = 24;
+    buf.extend_from_slice(&[0, 0]);
+    assert!(matches!(TgaImage::from_bytes(&buf), Err(TgaError::UnsupportedIndexSize)));
+
+    Ok(())
+}
+
+#[test]
+fn descriptor_flags_round_trip() -> Result<(), TgaError> {
+    // Alpha bits and top origin map to bits 0-3 and bit 5
+    let flags = TgaDescriptor { alpha_bits: 8, top_origin: true, ..TgaDescriptor::default() };
+    assert_eq!(flags.to_u8(), 0x28);
+    assert_eq!(TgaDescriptor::from_u8(0x28)?, flags);
+
+    // Set the flags on a header and round-trip through the header buffer
+    let mut header = TgaHeader { image_bit_depth: 32, ..TgaHeader::default() };
+    header.set_descriptor_flags(flags)?;
+    assert_eq!(header.descriptor, 0x28);
+    assert_eq!(TgaHeader::from_buf(header.to_buf())?.descriptor_flags()?, flags);
+
+    // Alpha bits must fit the bit depth, and reserved bits must be zero
+    header.image_bit_depth = 24;
+    assert!(matches!(header.set_descriptor_flags(flags), Err(TgaError::InvalidPixelDepth)));
+    assert_eq!(header.descriptor, 0x28);
+    assert!(matches!(TgaDescriptor::from_u8(0x40), Err(TgaError::InvalidArgument)));
+    header.descriptor = 0x80;
+    assert!(matches!(TgaHeader::from_buf(header.to_buf()), Err(TgaError::InvalidArgument)));
+
+    Ok(())
+}
+
+#[test]
+fn concat_images() -> Result<(), TgaError> {
+    let red = TgaColor::RGB24([0, 0, 255]);
+    let blue = TgaColor::RGB24([255, 0, 0]);
+    let left = TgaImage::new_with_color(TgaImageType::TrueColorImage, 2, 2, 24, red)?;
+    let mut right = TgaImage::new_with_color(TgaImageType::TrueColorImage, 2, 2, 24, blue)?;
+    right.set_top_origin(true);
+
+    // The seam is between columns 1 and 2
+    let joined = TgaImage::concat_horizontal(&[left.clone(), right.clone()])?;
+    assert_eq!((joined.header.width, joined.header.height), (4, 2));
+    for y in 0..2 {
+        assert_eq!(joined.get_pixel(1, y)?, red);
+        assert_eq!(joined.get_pixel(2, y)?, blue);
+    }
+
+    // Vertical concatenation stacks the images
+    let stacked = TgaImage::concat_vertical(&[left.clone(), right])?;
+    assert_eq!((stacked.header.width, stacked.header.height), (2, 4));
+    assert_eq!(stacked.get_pixel(0, 1)?, red);
+    assert_eq!(stacked.get_pixel(0, 2)?, blue);
+
+    // Mismatched sizes and formats are rejected
+    let tall = TgaImage::new(TgaImageType::TrueColorImage, 2, 3, 24)?;
+    assert!(matches!(TgaImage::concat_horizontal(&[left.clone(), tall]), Err(TgaError::InvalidSize)));
+    let grey = TgaImage::new(TgaImageType::BlackAndWhiteImage, 2, 2, 8)?;
+    assert!(matches!(TgaImage::concat_vertical(&[left, grey]), Err(TgaError::InvalidImageType)));
+    assert!(matches!(TgaImage::concat_horizontal(&[]), Err(TgaError::InvalidSize)));
+
+    // Color-mapped images must share a color map
+    let a = indexed_image(2, 2, &[TgaColor::RGB24([1, 1, 1]), TgaColor::RGB24([2, 2, 2])])?;
+    let joined = TgaImage::concat_horizontal(&[a.clone(), a.clone()])?;
+    assert_eq!(joined.color_map_entry(1)?, TgaColor::RGB24([2, 2, 2]));
+    let b = indexed_image(2, 2, &[TgaColor::RGB24([1, 1, 1]), TgaColor::RGB24([3, 3, 3])])?;
+    assert!(matches!(TgaImage::concat_horizontal(&[a.clone(), b]), Err(TgaError::InvalidColorMap)));
+    let mut shifted = a.clone();
+    shifted.header.color_map_first_index = 1;
+    assert!(matches!(TgaImage::concat_vertical(&[a, shifted]), Err(TgaError::InvalidColorMap)));
+
+    Ok(())
+}
+
+#[test]
+fn drop_color_map_keeps_indices() -> Result<(), TgaError> {
+    let mut image = indexed_image(2, 1, &[TgaColor::RGB24([1, 2, 3]), TgaColor::RGB24([4, 5, 6])])?;
+    image.set_pixel(1, 0, TgaColor::Greyscale([1]))?;
+    image.drop_color_map()?;
+
+    // The header no longer describes a color map
+    assert_eq!(image.header.image_type, TgaImageType::BlackAndWhiteImage);
+    assert!(!image.header.has_color_map);
+    assert_eq!(image.header.color_map_size, 0);
+    assert_eq!(image.header.color_map_bit_depth, 0);
+    image.verify()?;
+
+    // Indices are now greyscale values
+    assert_eq!(image.get_pixel(0, 0)?, TgaColor::Greyscale([0]));
+    assert_eq!(image.get_pixel(1, 0)?, TgaColor::Greyscale([1]));
+    assert!(matches!(image.drop_color_map(), Err(TgaError::InvalidImageType)));
+
+    Ok(())
+}
+
+#[test]
+fn invalid_color_map_errors() -> Result<(), TgaError> {
+    let image = indexed_image(2, 1, &[TgaColor::RGB24([1, 2, 3]), TgaColor::RGB24([4, 5, 6])])?;
+    TgaImage::from_bytes(&image.to_bytes())?;
+
+    // A declared color map without entries
+    let mut broken = image.clone();
+    broken.header.color_map_size = 0;
+    broken.color_map = vec![].into_boxed_slice();
+    assert!(matches!(TgaImage::from_bytes(&broken.to_bytes()), Err(TgaError::InvalidColorMap)));
+    assert!(matches!(broken.verify(), Err(TgaError::InvalidColorMap)));
+
+    // An unsupported color map bit depth
+    let mut broken = image.clone();
+    broken.header.color_map_bit_depth = 12;
+    assert!(matches!(TgaImage::from_bytes(&broken.to_bytes()), Err(TgaError::InvalidColorMap)));
+    assert!(matches!(broken.verify(), Err(TgaError::InvalidColorMap)));
+
+    // A color map that does not fit in the buffer
+    let mut buf = image.header.to_buf().to_vec();
+    buf.extend_from_slice(&[1, 2, 3, 4]);
+    assert!(matches!(TgaImage::from_bytes(&buf), Err(TgaError::InvalidColorMap)));
+
+    Ok(())
+}
+
+#[test]
+fn rotate_with_fill() -> Result<(), TgaError> {
+    let red = TgaColor::RGB24([0, 0, 255]);
+    let blue = TgaColor::RGB24([255, 0, 0]);
+    let square = TgaImage::new_with_color(TgaImageType::TrueColorImage, 10, 10, 24, red)?;
+
+    // The canvas grows to fit the rotated square, and the exposed corners are filled
+    let rotated = square.rotate(45.0, blue)?;
+    assert_eq!((rotated.header.width, rotated.header.height), (15, 15));
+    assert_eq!(rotated.get_pixel(7, 7)?, red);
+    assert_eq!(rotated.get_pixel(0, 0)?, blue);
+    assert_eq!(rotated.get_pixel(14, 14)?, blue);
+
+    // A quarter turn moves the top-left pixel to the top-right corner
+    let mut image = TgaImage::new(TgaImageType::BlackAndWhiteImage, 3, 2, 8)?;
+    image.set_pixel(0, 0, TgaColor::Greyscale([255]))?;
+    let rotated = image.rotate(90.0, TgaColor::Greyscale([0]))?;
+    assert_eq!((rotated.header.width, rotated.header.height), (2, 3));
+    assert_eq!(rotated.get_pixel(1, 0)?, TgaColor::Greyscale([255]));
+    assert!(matches!(image.rotate(f32::NAN, TgaColor::Greyscale([0])), Err(TgaError::InvalidArgument)));
+
+    Ok(())
+}
+
+#[test]
+fn load_many_frames() -> Result<(), TgaError> {
+    let mut frame = TgaImage::new(TgaImageType::TrueColorImage, 3, 2, 24)?;
+    let frame0_path = temp_path("test_frame0.tga")?;
+    frame.to_file(&frame0_path)?;
+    frame.set_pixel(1, 1, TgaColor::RGB24([0, 0, 255]))?;
+    let frame1_path = temp_path("test_frame1.tga")?;
+    frame.to_file(&frame1_path)?;
+    let frame2_path = temp_path("test_frame2.tga")?;
+    TgaImage::new(TgaImageType::TrueColorImage, 2, 2, 24)?.to_file(&frame2_path)?;
+
+    // Matching frames load in order
+    let frames = TgaImage::load_many(&[&frame0_path, &frame1_path])?;
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[1], frame);
+
+    // A frame of a different size is rejected
+    let result = TgaImage::load_many(&[&frame0_path, &frame1_path, &frame2_path]);
+    assert!(matches!(result, Err(TgaError::InvalidSize)));
+
+    // Failures report the index of the failing path
+    let result = TgaImage::load_many(&[frame0_path, temp_path("test_frame_missing.tga")?]);
+    assert!(matches!(result, Err(TgaError::InvalidFrame(1, e)) if matches!(*e, TgaError::FileOpen(_, _))));
+
+    Ok(())
+}
+
+#[test]
+fn const_color_constructors() {
+    // Constructors take RGB order and can be used in constants
+    const ORANGE: TgaColor = TgaColor::rgb24(255, 128, 0);
+    const GLASS: TgaColor = TgaColor::rgba(10, 20, 30, 40);
+    const PALETTE: [TgaColor; 3] = [TgaColor::BLACK_RGB24, TgaColor::WHITE_RGB24, ORANGE];
+    assert_eq!(ORANGE, TgaColor::RGB24([0, 128, 255]));
+    assert_eq!(GLASS, TgaColor::RGBA([30, 20, 10, 40]));
+    assert_eq!(PALETTE[1], TgaColor::RGB24([255, 255, 255]));
+    assert_eq!(TgaColor::TRANSPARENT_RGBA.to_rgba8(), [0, 0, 0, 0]);
+    assert_eq!(TgaColor::WHITE_RGBA.to_rgba8(), [255, 255, 255, 255]);
+}
+
+#[test]
+fn from_file_region_matches_crop() -> Result<(), TgaError> {
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 6, 5, 24)?;
+    for y in 0..5 {
+        for x in 0..6 {
+            image.set_pixel(x, y, TgaColor::RGB24([x as u8, y as u8, 9]))?;
+        }
+    }
+
+    // Compare with loading the whole file, for each combination of origin bits
+    let region_path = temp_path("test_region.tga")?;
+    for descriptor in [0x00, 0x10, 0x20, 0x30] {
+        let mut stored = image.clone();
+        stored.header.descriptor = descriptor;
+        stored.to_file(&region_path)?;
+        let full = TgaImage::from_file(&region_path)?;
+        let region = TgaImage::from_file_region(&region_path, 1, 2, 3, 2)?;
+        assert_eq!(region, full.crop(1, 2, 3, 2)?);
+    }
+
+    // The rectangle must be inside the image
+    let result = TgaImage::from_file_region(&region_path, 4, 0, 3, 1);
+    assert!(matches!(result, Err(TgaError::InvalidCoordinate)));
+
+    Ok(())
+}
+
+#[test]
+fn checker_pattern() -> Result<(), TgaError> {
+    let image = TgaImage::checker(4, 4, 1, TgaColor::BLACK_RGB24, TgaColor::WHITE_RGB24)?;
+    assert_eq!(image.header.image_type, TgaImageType::TrueColorImage);
+    assert_eq!(image.header.image_bit_depth, 24);
+
+    // Neighboring pixels alternate colors
+    for y in 0..4 {
+        for x in 0..4 {
+            let expected = if (x + y) % 2 == 0 { TgaColor::BLACK_RGB24 } else { TgaColor::WHITE_RGB24 };
+            assert_eq!(image.get_pixel(x, y)?, expected);
+        }
+    }
+
+    // RGBA colors create a 32-bit image with larger cells
+    let image = TgaImage::checker(4, 4, 2, TgaColor::TRANSPARENT_RGBA, TgaColor::WHITE_RGBA)?;
+    assert_eq!(image.header.alpha_bits(), 8);
+    assert_eq!(image.get_pixel(1, 1)?, TgaColor::TRANSPARENT_RGBA);
+    assert_eq!(image.get_pixel(2, 1)?, TgaColor::WHITE_RGBA);
+
+    // Both colors must share a format
+    assert!(matches!(TgaImage::checker(4, 4, 1, TgaColor::BLACK_RGB24, TgaColor::WHITE_RGBA), Err(TgaError::InvalidColor)));
+
+    Ok(())
+}
+
+#[test]
+fn alpha_bounds_single_pixel() -> Result<(), TgaError> {
+    let mut image = TgaImage::new_with_color(TgaImageType::TrueColorImage, 5, 5, 32, TgaColor::TRANSPARENT_RGBA)?;
+    assert_eq!(image.alpha_bounds()?, None);
+
+    // A single opaque pixel in the middle
+    image.set_pixel(2, 3, TgaColor::rgba(10, 20, 30, 1))?;
+    assert_eq!(image.alpha_bounds()?, Some((2, 3, 1, 1)));
+
+    // Only 32-bit images have alpha
+    let image = TgaImage::new(TgaImageType::TrueColorImage, 5, 5, 24)?;
+    assert!(matches!(image.alpha_bounds(), Err(TgaError::InvalidImageType)));
+
+    Ok(())
+}
+
+#[test]
+fn scanline_hashes_find_changed_row() -> Result<(), TgaError> {
+    let mut image = TgaImage::checker(4, 3, 1, TgaColor::BLACK_RGB24, TgaColor::WHITE_RGB24)?;
+    let before = image.scanline_hashes();
+    assert_eq!(before.len(), 3);
+
+    // Change one pixel and compare the hashes
+    image.set_pixel(2, 1, TgaColor::rgb24(1, 2, 3))?;
+    let after = image.scanline_hashes();
+    let changed: Vec<usize> = (0..3).filter(|&row| before[row] != after[row]).collect();
+    assert_eq!(changed, vec![1]);
+
+    Ok(())
+}
+
+#[test]
+fn image_type_channels() {
+    use TgaImageType::*;
+
+    // Every type against every bit depth in use
+    let depths = [0, 8, 15, 16, 24, 32];
+    let cases = [
+        (NoImage, [0, 0, 0, 0, 0, 0]),
+        (ColorMappedImage, [0, 1, 0, 1, 0, 0]),
+        (TrueColorImage, [0, 0, 3, 3, 3, 4]),
+        (BlackAndWhiteImage, [0, 1, 0, 2, 0, 0]),
+        (RleColorMappedImage, [0, 1, 0, 1, 0, 0]),
+        (RleTrueColorImage, [0, 0, 3, 3, 3, 4]),
+        (RleBlackAndWhiteImage, [0, 1, 0, 2, 0, 0]),
+    ];
+    for (image_type, channels) in cases {
+        for (bit_depth, expected) in depths.iter().zip(channels) {
+            assert_eq!(image_type.channels(*bit_depth), expected, "{:?} at {} bits", image_type, bit_depth);
+        }
+    }
+}
+
+#[test]
+fn shrink_fits_header_sizes() -> Result<(), TgaError> {
+    let mut image = indexed_image(3, 2, &[TgaColor::BLACK_RGB24, TgaColor::WHITE_RGB24])?;
+    image.drop_color_map()?;
+    image.shrink();
+    assert_eq!(image.color_map.len(), 0);
+    assert_eq!(image.data().len(), image.header.image_size());
+    image.verify()?;
+
+    // Buffers that disagree with the header are truncated or padded
+    image.header.id_size = 2;
+    image.data = vec![7; 10].into_boxed_slice();
+    image.shrink();
+    assert_eq!(&image.id[..], &[0, 0]);
+    assert_eq!(image.data(), &[7; 6]);
+    image.verify()?;
+
+    Ok(())
+}
+
+#[test]
+fn try_clone_matches_clone() -> Result<(), TgaError> {
+    let mut image = indexed_image(3, 2, &[TgaColor::BLACK_RGB24, TgaColor::WHITE_RGB24])?;
+    image.set_pixel(1, 1, TgaColor::Greyscale([1]))?;
+    image.set_gamma(2.2);
+    assert_eq!(image.try_clone()?, image.clone());
+
+    Ok(())
+}
+
+#[test]
+fn apply_lut_inversion() -> Result<(), TgaError> {
+    let mut image = TgaImage::checker(4, 4, 1, TgaColor::rgba(10, 20, 30, 40), TgaColor::rgba(200, 150, 100, 50))?;
+    let mut inverted = image.clone();
+    inverted.invert()?;
+
+    // An inversion table gives the same result as invert, and keeps alpha
+    let mut lut = [0; 256];
+    for (value, entry) in lut.iter_mut().enumerate() {
+        *entry = 255 - value as u8;
+    }
+    image.apply_lut(&lut)?;
+    assert_eq!(image, inverted);
+    assert_eq!(image.get_pixel(0, 0)?, TgaColor::rgba(245, 235, 225, 40));
+
+    // Color-mapped images are rejected
+    let mut indexed = indexed_image(2, 2, &[TgaColor::BLACK_RGB24])?;
+    assert!(matches!(indexed.apply_lut(&lut), Err(TgaError::InvalidImageType)));
+
+    Ok(())
+}
+
+#[test]
+fn rle_encoded_size_estimate() -> Result<(), TgaError> {
+    // A solid image compresses to one run packet per row
+    let solid = TgaImage::new_with_color(TgaImageType::TrueColorImage, 16, 4, 24, TgaColor::WHITE_RGB24)?;
+    assert_eq!(solid.rle_encoded_size()?, 4 * (1 + 3));
+
+    // Alternating pixels need raw packets with a header byte each
+    let noisy = TgaImage::checker(16, 4, 1, TgaColor::BLACK_RGB24, TgaColor::WHITE_RGB24)?;
+    assert!(noisy.rle_encoded_size()? > noisy.header.image_size());
+
+    // The estimate matches the written pixel data
+    let mut rle = noisy.clone();
+    rle.header.image_type = TgaImageType::RleTrueColorImage;
+    assert_eq!(rle.to_bytes().len(), 18 + noisy.rle_encoded_size()?);
+
+    Ok(())
+}
+
+#[test]
+fn to_file_auto_picks_smaller() -> Result<(), TgaError> {
+    // A solid image is written run-length encoded
+    let solid = TgaImage::new_with_color(TgaImageType::TrueColorImage, 16, 4, 24, TgaColor::WHITE_RGB24)?;
+    let auto_rle_path = temp_path("test_auto_rle.tga")?;
+    assert_eq!(solid.to_file_auto(&auto_rle_path)?, TgaImageType::RleTrueColorImage);
+    let read = TgaImage::from_file(&auto_rle_path)?;
+    assert_eq!(read.header.image_type, TgaImageType::RleTrueColorImage);
+    assert_eq!(read.data(), solid.data());
+
+    // A noisy image is written uncompressed
+    let noisy = TgaImage::checker(16, 4, 1, TgaColor::BLACK_RGB24, TgaColor::WHITE_RGB24)?;
+    let auto_raw_path = temp_path("test_auto_raw.tga")?;
+    assert_eq!(noisy.to_file_auto(&auto_raw_path)?, TgaImageType::TrueColorImage);
+    assert_eq!(TgaImage::from_file(&auto_raw_path)?, noisy);
+
+    Ok(())
+}
+
+#[test]
+fn header_from_file_reads_only_header() -> Result<(), TgaError> {
+    let image = TgaImage::new(TgaImageType::TrueColorImage, 7, 3, 32)?;
+    let header_path = temp_path("test_header.tga")?;
+    image.to_file(&header_path)?;
+    assert_eq!(TgaImage::header_from_file(&header_path)?, TgaImage::from_file(&header_path)?.header);
+
+    let header_only_path = temp_path("test_header_only.tga")?;
+    // The pixel data is not needed
+    std::fs::write(&header_only_path, image.header.to_buf()).unwrap();
+    assert!(matches!(TgaImage::from_file_versioned(&header_only_path), Err(TgaError::InvalidSize)));
+    assert_eq!(TgaImage::header_from_file(&header_only_path)?, image.header);
+
+    let header_short_path = temp_path("test_header_short.tga")?;
+    // Files shorter than a header are rejected
+    std::fs::write(&header_short_path, &image.header.to_buf()[..10]).unwrap();
+    assert!(matches!(TgaImage::header_from_file(&header_short_path), Err(TgaError::InvalidSize)));
+
+    Ok(())
+}
+
+#[test]
+fn color_greyscale_conversions() {
+    // Color to greyscale uses luminance weights and drops alpha
+    assert_eq!(TgaColor::rgb24(255, 0, 0).to_greyscale(), TgaColor::Greyscale([76]));
+    assert_eq!(TgaColor::rgba(0, 255, 0, 0).to_greyscale(), TgaColor::Greyscale([150]));
+    assert_eq!(TgaColor::RGB16([0x1f, 0x00]).to_greyscale(), TgaColor::Greyscale([29]));
+    assert_eq!(TgaColor::WHITE_RGB24.to_greyscale(), TgaColor::Greyscale([255]));
+    assert_eq!(TgaColor::Greyscale([9]).to_greyscale(), TgaColor::Greyscale([9]));
+
+    // Greyscale to color replicates the value
+    assert_eq!(TgaColor::Greyscale([9]).to_rgb24(), TgaColor::rgb24(9, 9, 9));
+    assert_eq!(TgaColor::rgba(1, 2, 3, 4).to_rgb24(), TgaColor::rgb24(1, 2, 3));
+    assert_eq!(TgaColor::RGB16([0x00, 0x7c]).to_rgb24(), TgaColor::rgb24(255, 0, 0));
+}
+
+#[test]
+fn set_pixel_large_image_offset() -> Result<(), TgaError> {
+    // Offsets past u16::MAX must not wrap
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 1000, 1000, 24)?;
+    image.set_pixel(998, 999, TgaColor::rgb24(1, 2, 3))?;
+    image.set_pixel(999, 0, TgaColor::rgb24(4, 5, 6))?;
+    assert_eq!(image.get_pixel(998, 999)?, TgaColor::rgb24(1, 2, 3));
+    assert_eq!(image.get_pixel(999, 0)?, TgaColor::rgb24(4, 5, 6));
+    assert_eq!(image.byte_offset(999, 0), Some(image.data().len() - 3));
+
+    // Only the two pixels were written
+    assert_eq!(image.data().iter().filter(|&&byte| byte != 0).count(), 6);
+
+    Ok(())
+}
+
+#[test]
+fn new_color_mapped_palette() -> Result<(), TgaError> {
+    let palette = [TgaColor::rgb24(255, 0, 0), TgaColor::rgb24(0, 0, 255)];
+    let mut image = TgaImage::new_color_mapped(2, 2, &palette)?;
+    assert!(image.header.has_color_map);
+    assert_eq!(image.header.color_map_size, 2);
+    assert_eq!(image.header.color_map_bit_depth, 24);
+    image.verify()?;
+
+    // Resolve the indices through the palette
+    image.set_pixel(1, 1, TgaColor::Greyscale([1]))?;
+    let resolved = image.resolve_color_map()?;
+    assert_eq!(resolved.get_pixel(0, 0)?, palette[0]);
+    assert_eq!(resolved.get_pixel(1, 1)?, palette[1]);
+
+    // Reject empty, oversized, mixed, and greyscale palettes
+    assert!(matches!(TgaImage::new_color_mapped(2, 2, &[]), Err(TgaError::InvalidColorMap)));
+    assert!(matches!(TgaImage::new_color_mapped(2, 2, &[TgaColor::BLACK_RGB24; 257]), Err(TgaError::InvalidColorMap)));
+    assert!(matches!(TgaImage::new_color_mapped(2, 2, &[TgaColor::BLACK_RGB24, TgaColor::BLACK_RGBA]), Err(TgaError::InvalidColorMap)));
+    assert!(matches!(TgaImage::new_color_mapped(2, 2, &[TgaColor::Greyscale([0])]), Err(TgaError::InvalidColorMap)));
+    assert!(matches!(TgaImage::new_color_mapped(2, 2, &[TgaColor::GreyscaleAlpha([0, 255])]), Err(TgaError::InvalidColorMap)));
+    assert!(matches!(TgaImage::new_color_mapped(2, 2, &[TgaColor::RGB16([0, 0]), TgaColor::GreyscaleAlpha([0, 255])]), Err(TgaError::InvalidColorMap)));
+
+    Ok(())
+}
+
+#[test]
+fn pixel_format_by_depth() -> Result<(), TgaError> {
+    let format = |byte_depth, channels, has_alpha, is_indexed| {PixelFormat { byte_depth, channels, has_alpha, is_indexed }};
+
+    // 8-bit greyscale and color-mapped images
+    assert_eq!(TgaImage::new(TgaImageType::BlackAndWhiteImage, 1, 1, 8)?.pixel_format(), format(1, 1, false, false));
+    assert_eq!(indexed_image(1, 1, &[TgaColor::BLACK_RGB24])?.pixel_format(), format(1, 1, false, true));
+
+    // 16-bit images only have alpha if the descriptor declares it
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 1, 1, 16)?;
+    assert_eq!(image.pixel_format(), format(2, 3, false, false));
+    image.header.set_alpha_bits(1)?;
+    assert_eq!(image.pixel_format(), format(2, 3, true, false));
+
+    // 24-bit and 32-bit true color images
+    assert_eq!(TgaImage::new(TgaImageType::TrueColorImage, 1, 1, 24)?.pixel_format(), format(3, 3, false, false));
+    assert_eq!(TgaImage::new(TgaImageType::RleTrueColorImage, 1, 1, 32)?.pixel_format(), format(4, 4, true, false));
+
+    Ok(())
+}
+
+#[test]
+fn greyscale_alpha_round_trip() -> Result<(), TgaError> {
+    let mut image = TgaImage::new(TgaImageType::BlackAndWhiteImage, 2, 1, 16)?;
+    image.header.set_alpha_bits(8)?;
+    image.set_pixel(0, 0, TgaColor::GreyscaleAlpha([200, 128]))?;
+    image.set_pixel(1, 0, TgaColor::GreyscaleAlpha([10, 0]))?;
+
+    // Greyscale pixels with alpha survive a write and read
+    let read = TgaImage::from_bytes(&image.to_bytes())?;
+    assert_eq!(read.header.alpha_bits(), 8);
+    assert_eq!(read.get_pixel(0, 0)?, TgaColor::GreyscaleAlpha([200, 128]));
+    assert_eq!(read.as_rgba_vec()?, vec![200, 200, 200, 128, 10, 10, 10, 0]);
+    assert!(read.pixel_format().has_alpha);
+
+    // Only black and white images take greyscale alpha colors
+    let mut rgb = TgaImage::new(TgaImageType::TrueColorImage, 1, 1, 16)?;
+    assert!(matches!(rgb.set_pixel(0, 0, TgaColor::GreyscaleAlpha([0, 0])), Err(TgaError::InvalidColor)));
+    assert!(matches!(rgb.header.set_alpha_bits(8), Err(TgaError::InvalidPixelDepth)));
+    Ok(())
+}
+
+#[test]
+fn to_file_atomic_writes_complete_file() -> Result<(), TgaError> {
+    let image = TgaImage::checker(9, 7, 2, TgaColor::BLACK_RGB24, TgaColor::WHITE_RGB24)?;
+
+    let atomic_dir = temp_dir("atomic")?;
+    let atomic_path = atomic_dir.join("test_atomic.tga");
+    // Overwrite an existing file with the complete image
+    std::fs::write(&atomic_path, b"stale").map_err(|e| {TgaError::FileWrite(e, None)})?;
+    image.to_file_atomic(&atomic_path)?;
+    let bytes = std::fs::read(&atomic_path).map_err(|e| {TgaError::FileRead(e, None)})?;
+    assert_eq!(bytes, image.to_bytes());
+    assert_eq!(TgaImage::from_bytes(&bytes)?.data, image.data);
+
+    // No temporary file is left behind
+    assert!(!atomic_temp_files_exist(&atomic_dir)?);
+
+    // A missing directory fails without creating anything
+    assert!(matches!(image.to_file_atomic(temp_path("missing_dir")?.join("test_atomic.tga")), Err(TgaError::FileOpen(_, _))));
+
+    let atomic_dir_path = atomic_dir.join("test_atomic_dir.tga");
+    // A failed rename removes the temporary file
+    std::fs::create_dir_all(atomic_dir_path.join("inner")).map_err(|e| {TgaError::FileWrite(e, None)})?;
+    assert!(matches!(image.to_file_atomic(&atomic_dir_path), Err(TgaError::FileWrite(_, _))));
+    assert!(!atomic_temp_files_exist(&atomic_dir)?);
+    Ok(())
+}
+
+#[test]
+fn to_file_atomic_concurrent_writes() -> Result<(), TgaError> {
+    let images: Vec<TgaImage> = (0..8)
+        .map(|i| TgaImage::new_with_color(TgaImageType::TrueColorImage, 64, 64, 24, TgaColor::rgb24(i, i, i)))
+        .collect::<Result<_, _>>()?;
+
+    // Threads writing to the same destination each use their own temporary file
+    let atomic_threads_dir = temp_dir("atomic_threads")?;
+    let atomic_threads_path = atomic_threads_dir.join("test_atomic_threads.tga");
+    std::thread::scope(|scope| {
+        for image in &images {
+            let path = &atomic_threads_path;
+            scope.spawn(move || image.to_file_atomic(path));
+        }
+    });
+
+    // The result is exactly one of the written images
+    let read = TgaImage::from_file(&atomic_threads_path)?;
+    assert!(images.contains(&read));
+    assert!(!atomic_temp_files_exist(&atomic_threads_dir)?);
+    Ok(())
+}
+
+/// Returns true if a temporary file of `to_file_atomic` is left in `dir`.
+fn atomic_temp_files_exist(dir: &Path) -> Result<bool, TgaError> {
+    let read_error = |e: std::io::Error| {TgaError::FileRead(e, Some(dir.to_path_buf()))};
+    for entry in std::fs::read_dir(dir).map_err(read_error)? {
+        if entry.map_err(read_error)?.file_name().to_string_lossy().ends_with(".tmp") {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+#[test]
+fn alpha_queries() -> Result<(), TgaError> {
+    let mut image = TgaImage::checker(3, 3, 1, TgaColor::BLACK_RGBA, TgaColor::WHITE_RGBA)?;
+    assert!(image.is_fully_opaque()?);
+    assert!(!image.has_transparency()?);
+
+    // A single transparent pixel makes the image transparent
+    image.set_pixel(2, 1, TgaColor::rgba(10, 20, 30, 0))?;
+    assert!(!image.is_fully_opaque()?);
+    assert!(image.has_transparency()?);
+
+    // Images without alpha are always opaque
+    let rgb = TgaImage::new(TgaImageType::TrueColorImage, 2, 2, 24)?;
+    assert!(rgb.is_fully_opaque()?);
+    Ok(())
+}
+
+#[test]
+fn color_key_masks_background() -> Result<(), TgaError> {
+    let magenta = TgaColor::rgb24(255, 0, 255);
+    let sprite = TgaColor::rgb24(10, 200, 30);
+    let mut image = TgaImage::new_with_color(TgaImageType::TrueColorImage, 3, 2, 24, magenta)?;
+    image.set_pixel(1, 0, sprite)?;
+    image.set_pixel(2, 1, sprite)?;
+
+    // Keyed pixels are transparent and every other pixel is opaque
+    let keyed = image.color_key(magenta)?;
+    assert_eq!(keyed.header.image_bit_depth, 32);
+    assert_eq!(keyed.header.alpha_bits(), 8);
+    assert_eq!(keyed.get_pixel(0, 0)?, TgaColor::rgba(255, 0, 255, 0));
+    assert_eq!(keyed.get_pixel(1, 0)?, TgaColor::rgba(10, 200, 30, 255));
+    assert_eq!(keyed.get_pixel(2, 1)?, TgaColor::rgba(10, 200, 30, 255));
+    assert_eq!(keyed.alpha_bounds()?, Some((1, 0, 2, 2)));
+
+    // Only 24-bit true color images and keys are accepted
+    assert!(matches!(image.color_key(TgaColor::BLACK_RGBA), Err(TgaError::InvalidColor)));
+    assert!(matches!(keyed.color_key(magenta), Err(TgaError::InvalidImageType)));
+    Ok(())
+}
+
+#[test]
+fn file_errors_include_path() -> Result<(), TgaError> {
+    // Errors from file-based functions name the file
+    let missing = temp_path("missing_dir")?.join("test_missing.tga");
+    let result = TgaImage::from_file_versioned(&missing);
+    assert!(matches!(&result, Err(TgaError::FileOpen(_, Some(path))) if *path == missing));
+    let message = result.err().map(|e| {e.to_string()}).unwrap_or_default();
+    assert!(message.contains(&missing.display().to_string()), "{}", message);
+
+    let image = TgaImage::default();
+    let out = temp_path("missing_dir")?.join("test_out.tga");
+    let message = image.to_file(&out).err().map(|e| {e.to_string()}).unwrap_or_default();
+    assert!(message.starts_with(&format!("failed to open {}", out.display())), "{}", message);
+    Ok(())
+}
+
+#[test]
+fn stream_writer_matches_buffered() -> Result<(), TgaError> {
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 2, 3, 24)?;
+    for y in 0..3 {
+        image.set_pixel(0, y, TgaColor::rgb24(y as u8, 10, 20))?;
+        image.set_pixel(1, y, TgaColor::rgb24(30, y as u8, 40))?;
+    }
+
+    // Stream the stored rows one at a time
+    let mut stream = TgaImage::begin_stream(Vec::new(), image.header)?;
+    for row in image.data.chunks_exact(6) {
+        stream.write_row(row)?;
+    }
+    assert!(matches!(stream.write_row(&[0; 6]), Err(TgaError::InvalidSize)));
+    let buf = stream.finish()?;
+    assert_eq!(buf, image.to_bytes());
+    assert_eq!(TgaImage::from_bytes(&buf)?, image);
+
+    // Rows must have the right length and every row must be written
+    let mut stream = TgaImage::begin_stream(Vec::new(), image.header)?;
+    assert!(matches!(stream.write_row(&[0; 5]), Err(TgaError::InvalidSize)));
+    stream.write_row(&[0; 6])?;
+    assert!(matches!(stream.finish(), Err(TgaError::InvalidSize)));
+    Ok(())
+}
+
+#[test]
+fn resize_with_each_filter() -> Result<(), TgaError> {
+    let image = TgaImage::checker(8, 6, 2, TgaColor::BLACK_RGBA, TgaColor::WHITE_RGBA)?;
+
+    // Every filter produces a valid image of the requested size
+    for filter in [ResizeFilter::Nearest, ResizeFilter::Bilinear, ResizeFilter::Box] {
+        let resized = image.resize(4, 3, filter)?;
+        assert_eq!((resized.header.width, resized.header.height), (4, 3), "{:?}", filter);
+        assert_eq!(resized.header.image_bit_depth, 32);
+        assert_eq!(TgaImage::from_bytes(&resized.to_bytes())?, resized);
+    }
+    assert_eq!(image.resize(4, 3, ResizeFilter::Nearest)?, image.resize_nearest(4, 3)?);
+
+    // The box filter only shrinks
+    assert!(matches!(image.resize(16, 12, ResizeFilter::Box), Err(TgaError::InvalidSize)));
+    Ok(())
+}
+
+#[test]
+fn from_bytes_decodes_rle() -> Result<(), TgaError> {
+    // A 3x2 greyscale image whose run packet spans both rows
+    let mut buf = TgaImage::new(TgaImageType::RleBlackAndWhiteImage, 3, 2, 8)?.header.to_buf().to_vec();
+    buf.extend_from_slice(&[0x01, 10, 20, 0x83, 30]);
+    let image = TgaImage::from_bytes_exact(&buf)?;
+    assert_eq!(image.data(), &[10, 20, 30, 30, 30, 30]);
+    assert_eq!(image.state, TgaImageState::Uncompressed);
+
+    // Pixels can be read and written like an uncompressed image
+    let mut image = image;
+    assert_eq!(image.get_pixel(0, 1)?, TgaColor::Greyscale([10]));
+    image.set_pixel(2, 0, TgaColor::Greyscale([99]))?;
+    assert_eq!(TgaImage::from_bytes(&image.to_bytes())?, image);
+
+    // Truncated packets are rejected
+    assert!(matches!(TgaImage::from_bytes(&buf[..buf.len() - 1]), Err(TgaError::InvalidSize)));
+
+    // Color-mapped images keep their indices
+    let mapped = indexed_image(2, 2, &[TgaColor::BLACK_RGB24])?;
+    assert_eq!(TgaImage::from_bytes(&mapped.to_bytes())?.state, TgaImageState::ColorMapped);
+    Ok(())
+}
+
+#[test]
+fn to_file_rle_round_trip() -> Result<(), TgaError> {
+    let image = TgaImage::checker(32, 8, 8, TgaColor::BLACK_RGB24, TgaColor::WHITE_RGB24)?;
+    let rle_path = temp_path("test_rle.tga")?;
+    image.to_file_rle(&rle_path)?;
+
+    // The file is smaller than the uncompressed image and decodes to the same pixels
+    let size = std::fs::metadata(&rle_path).map_err(|e| {TgaError::FileRead(e, None)})?.len() as usize;
+    assert!(size < image.header.file_size());
+    let read = TgaImage::from_file(&rle_path)?;
+    assert_eq!(read.header.image_type, TgaImageType::RleTrueColorImage);
+    assert_eq!(read.data(), image.data());
+    assert_eq!(image.header.image_type, TgaImageType::TrueColorImage);
+
+    // Greyscale images are written as RleBlackAndWhiteImage
+    let grey = TgaImage::new(TgaImageType::BlackAndWhiteImage, 4, 4, 8)?;
+    let rle_grey_path = temp_path("test_rle_grey.tga")?;
+    grey.to_file_rle(&rle_grey_path)?;
+    assert_eq!(TgaImage::header_from_file(&rle_grey_path)?.image_type, TgaImageType::RleBlackAndWhiteImage);
+
+    // The in-memory encoding matches the file
+    assert_eq!(grey.to_bytes_rle()?, std::fs::read(&rle_grey_path).unwrap());
+    let mut none = TgaImage::default();
+    none.header.image_type = TgaImageType::NoImage;
+    assert!(matches!(none.to_bytes_rle(), Err(TgaError::InvalidImageType)));
+    Ok(())
+}
+
+#[test]
+fn get_pixel_resolved_uses_color_map() -> Result<(), TgaError> {
+    let red = TgaColor::rgb24(255, 0, 0);
+    let mut image = indexed_image(2, 2, &[TgaColor::BLACK_RGB24, red])?;
+    image.set_pixel(1, 0, TgaColor::Greyscale([1]))?;
+
+    // Color-mapped pixels resolve to their color map entries
+    let read = TgaImage::from_bytes(&image.to_bytes())?;
+    assert_eq!(read.get_pixel(1, 0)?, TgaColor::Greyscale([1]));
+    assert_eq!(read.get_pixel_resolved(1, 0)?, red);
+    assert_eq!(read.get_pixel_resolved(0, 1)?, TgaColor::BLACK_RGB24);
+
+    // Indices outside of the color map are rejected
+    image.set_pixel(0, 0, TgaColor::Greyscale([5]))?;
+    assert!(matches!(image.get_pixel_resolved(0, 0), Err(TgaError::InvalidColor)));
+
+    // Other images return their pixels unchanged
+    let rgb = TgaImage::new_with_color(TgaImageType::TrueColorImage, 1, 1, 24, red)?;
+    assert_eq!(rgb.get_pixel_resolved(0, 0)?, red);
+    Ok(())
+}
+
+#[test]
+fn to_color_mapped_round_trip() -> Result<(), TgaError> {
+    // Few colors are kept exactly
+    let image = TgaImage::checker(6, 4, 2, TgaColor::rgba(255, 0, 255, 0), TgaColor::rgba(10, 20, 30, 255))?;
+    let mapped = image.to_color_mapped()?;
+    assert_eq!(mapped.header.image_type, TgaImageType::ColorMappedImage);
+    assert_eq!(mapped.header.image_bit_depth, 8);
+    assert_eq!((mapped.header.color_map_size, mapped.header.color_map_bit_depth), (2, 32));
+    let read = TgaImage::from_bytes(&mapped.to_bytes())?;
+    assert_eq!(read.resolve_color_map()?, image);
+
+    // Many colors are reduced to at most 256 entries
+    let mut gradient = TgaImage::new(TgaImageType::TrueColorImage, 64, 64, 24)?;
+    for y in 0..64 {
+        for x in 0..64 {
+            gradient.set_pixel(x, y, TgaColor::rgb24(x as u8 * 4, y as u8 * 4, 128))?;
+        }
+    }
+    let mapped = gradient.to_color_mapped()?;
+    assert!(mapped.header.color_map_size <= 256);
+    let resolved = mapped.resolve_color_map()?;
+    let diff = resolved.diff(&gradient)?;
+    assert!(diff.max_channel_diff <= 32, "{}", diff.max_channel_diff);
+
+    // Only true color images can be mapped
+    assert!(matches!(mapped.to_color_mapped(), Err(TgaError::InvalidImageType)));
+    Ok(())
+}
+
+#[test]
+fn get_pixel_by_depth() -> Result<(), TgaError> {
+    // The color variant follows the image's bit depth
+    let cases = [
+        (TgaImageType::BlackAndWhiteImage, 8, TgaColor::Greyscale([7])),
+        (TgaImageType::BlackAndWhiteImage, 16, TgaColor::GreyscaleAlpha([7, 8])),
+        (TgaImageType::TrueColorImage, 16, TgaColor::RGB16([7, 8])),
+        (TgaImageType::TrueColorImage, 24, TgaColor::RGB24([7, 8, 9])),
+        (TgaImageType::TrueColorImage, 32, TgaColor::RGBA([7, 8, 9, 10])),
+    ];
+    for (image_type, bit_depth, color) in cases {
+        let mut image = TgaImage::new(image_type, 3, 2, bit_depth)?;
+        image.set_pixel(2, 1, color)?;
+        assert_eq!(image.get_pixel(2, 1)?, color);
+        assert_eq!(image.get_pixel(0, 0)?.byte_depth(), color.byte_depth());
+
+        // Coordinates outside of the image are rejected
+        assert!(matches!(image.get_pixel(3, 0), Err(TgaError::InvalidCoordinate)));
+        assert!(matches!(image.get_pixel(0, 2), Err(TgaError::InvalidCoordinate)));
+    }
+    Ok(())
+}
+
+#[test]
+fn to_bytes_versioned_footer() -> Result<(), TgaError> {
+    // V2 always ends with a footer, even without an extension area
+    let image = TgaImage::new(TgaImageType::TrueColorImage, 2, 2, 24)?;
+    let buf = image.to_bytes_versioned(TgaVersion::V2);
+    assert_eq!(buf.len(), image.header.file_size() + 26);
+    assert_eq!(&buf[buf.len() - 18..], b"TRUEVISION-XFILE.\0");
+    assert_eq!(&buf[buf.len() - 26..buf.len() - 18], &[0; 8]);
+    assert_eq!(TgaImage::from_bytes_exact(&buf)?, image);
+    let v2_forced_path = temp_path("test_v2_forced.tga")?;
+    image.to_file_versioned(&v2_forced_path, TgaVersion::V2)?;
+    assert_eq!(TgaImage::from_file_versioned(&v2_forced_path)?.1, TgaVersion::V2);
+
+    // V1 drops the extension area and footer
+    let mut image = image;
+    image.set_gamma(2.2);
+    let buf = image.to_bytes_versioned(TgaVersion::V1);
+    assert_eq!(buf.len(), image.header.file_size());
+    assert_eq!(image.to_bytes_versioned(TgaVersion::V2), image.to_bytes());
+    Ok(())
+}
+
+#[test]
+fn extension_area_round_trip() -> Result<(), TgaError> {
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 2, 2, 32)?;
+    assert_eq!(image.extension_area(), None);
+
+    // Every field survives a write and read
+    let area = TgaExtensionArea {
+        author_name: "Ada".to_string(),
+        author_comments: "first line\nsecond line".to_string(),
+        date_time: [10, 16, 2026, 12, 30, 5],
+        job_name: "sprites".to_string(),
+        job_time: [1, 2, 3],
+        software_id: "rtga".to_string(),
+        software_version: 102,
+        gamma: Some(2.2),
+        attributes_type: 3
+    };
+    image.set_extension_area(&area)?;
+    let read = TgaImage::from_bytes(&image.to_bytes())?;
+    assert_eq!(read.extension_area(), Some(area.clone()));
+    assert_eq!(read.gamma(), Some(2.2));
+
+    // Text that does not fit is rejected without changing the image
+    let long = TgaExtensionArea { author_name: "x".repeat(41), ..area.clone() };
+    assert!(matches!(image.set_extension_area(&long), Err(TgaError::InvalidArgument)));
+    let many_lines = TgaExtensionArea { author_comments: "a\nb\nc\nd\ne".to_string(), ..area.clone() };
+    assert!(matches!(image.set_extension_area(&many_lines), Err(TgaError::InvalidArgument)));
+    assert_eq!(image.extension_area(), Some(area));
+
+    // An unused gamma value is stored as unused
+    image.set_extension_area(&TgaExtensionArea::default())?;
+    assert_eq!(image.gamma(), None);
+    Ok(())
+}
+
+#[test]
+fn extension_area_table_offsets_are_cleared() -> Result<(), TgaError> {
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 4, 1, 24)?;
+    image.set_gamma(2.2);
+
+    // A file whose extension area points to a key color and tables after it
+    let mut bytes = image.to_bytes();
+    let footer = bytes.len() - 26;
+    let extension = u32::from_le_bytes(bytes[footer..footer + 4].try_into().unwrap()) as usize;
+    bytes[extension + 470..extension + 474].copy_from_slice(&[1, 2, 3, 4]);
+    for offset in [482, 486, 490] {
+        bytes[extension + offset..extension + offset + 4].copy_from_slice(&(footer as u32).to_le_bytes());
+    }
+
+    // Writing with a different layout clears the table offsets but keeps the other fields
+    let image = TgaImage::from_bytes(&bytes)?;
+    let rle = image.to_bytes_rle()?;
+    let footer = rle.len() - 26;
+    let moved = u32::from_le_bytes(rle[footer..footer + 4].try_into().unwrap()) as usize;
+    assert_ne!(moved, extension);
+    assert_eq!(rle[moved + 470..moved + 474], [1, 2, 3, 4]);
+    assert!(rle[moved + 482..moved + 494].iter().all(|&b| b == 0));
+    assert_eq!(TgaImage::from_bytes(&rle)?.gamma(), Some(2.2));
+    Ok(())
+}
+
+#[test]
+fn developer_tags_round_trip() -> Result<(), TgaError> {
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 2, 2, 24)?;
+    image.set_developer_tag(42, b"build-1234".to_vec());
+    image.set_developer_tag(7, vec![1, 2, 3]);
+    image.set_developer_tag(42, b"build-5678".to_vec());
+    image.set_gamma(2.2);
+
+    let developer_path = temp_path("test_developer.tga")?;
+    // Tags survive a write and read, along with the extension area
+    image.to_file(&developer_path)?;
+    let (read, version) = TgaImage::from_file_versioned(&developer_path)?;
+    assert_eq!(version, TgaVersion::V2);
+    assert_eq!(read.developer_tags().len(), 2);
+    assert_eq!(read.developer_tag(42), Some(&b"build-5678"[..]));
+    assert_eq!(read.developer_tag(7), Some(&[1, 2, 3][..]));
+    assert_eq!(read.gamma(), Some(2.2));
+    assert_eq!(TgaImage::from_bytes_exact(&image.to_bytes())?, read);
+
+    // Removed tags are no longer written
+    let mut read = read;
+    assert_eq!(read.remove_developer_tag(7).map(|tag| tag.data), Some(vec![1, 2, 3]));
+    assert_eq!(read.remove_developer_tag(7), None);
+    assert_eq!(TgaImage::from_bytes(&read.to_bytes())?.developer_tags().len(), 1);
+    Ok(())
+}
+
+#[test]
+fn reader_writer_round_trip() -> Result<(), TgaError> {
+    let mut image = TgaImage::checker(4, 3, 1, TgaColor::BLACK_RGBA, TgaColor::WHITE_RGBA)?;
+    image.set_gamma(1.8);
+
+    // Write to and read from an in-memory cursor
+    let mut cursor = std::io::Cursor::new(Vec::new());
+    image.to_writer(&mut cursor)?;
+    assert_eq!(cursor.get_ref(), &image.to_bytes());
+    cursor.set_position(0);
+    assert_eq!(TgaImage::from_reader(&mut cursor)?, image);
+
+    // Truncated data is rejected
+    let bytes = image.to_bytes();
+    assert!(matches!(TgaImage::from_reader(&mut &bytes[..10]), Err(TgaError::InvalidSize)));
+    Ok(())
+}
+
+#[test]
+fn bytes_round_trip_is_exact() -> Result<(), TgaError> {
+    // A 2x1 color-mapped image with an id and a two-entry color map
+    let mut header = TgaImage::new(TgaImageType::ColorMappedImage, 2, 1, 8)?.header;
+    header.id_size = 3;
+    header.has_color_map = true;
+    header.color_map_size = 2;
+    header.color_map_bit_depth = 24;
+    let mut bytes = header.to_buf().to_vec();
+    bytes.extend_from_slice(b"abc");
+    bytes.extend_from_slice(&[0, 0, 0, 255, 255, 255]);
+    bytes.extend_from_slice(&[1, 0]);
+
+    // The image is written back byte for byte without touching the filesystem
+    let image = TgaImage::from_bytes(&bytes)?;
+    assert_eq!(image.to_bytes(), bytes);
+    assert_eq!(image.get_pixel_resolved(0, 0)?, TgaColor::WHITE_RGB24);
+    Ok(())
+}
+
+#[test]
+fn new_color_mapped_image_round_trips() -> Result<(), TgaError> {
+    // New color-mapped images get a greyscale color map that survives a round trip
+    for (image_type, bit_depth) in [(TgaImageType::ColorMappedImage, 8), (TgaImageType::RleColorMappedImage, 16)] {
+        let mut image = TgaImage::new(image_type, 2, 2, bit_depth)?;
+        assert_eq!(image.header.color_map_size, 256);
+        assert_eq!(image.get_pixel_resolved(0, 0)?, TgaColor::BLACK_RGB24);
+        image.set_pixel(1, 1, TgaColor::from_slice(&[200, 0][..bit_depth as usize / 8])?)?;
+        assert_eq!(image.get_pixel_resolved(1, 1)?, TgaColor::RGB24([200, 200, 200]));
+        assert_eq!(TgaImage::from_bytes(&image.to_bytes())?, image);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn scanline_decoder_yields_rows() -> Result<(), TgaError> {
+    // A 3x2 greyscale image with an id whose run packet spans both rows
+    let mut header = TgaImage::new(TgaImageType::RleBlackAndWhiteImage, 3, 2, 8)?.header;
+    header.id_size = 4;
+    let mut buf = header.to_buf().to_vec();
+    buf.extend_from_slice(b"scan");
+    buf.extend_from_slice(&[0x01, 10, 20, 0x83, 30]);
+
+    let mut decoder = TgaScanlineDecoder::new(&buf[..])?;
+    assert_eq!(decoder.header().width, 3);
+    assert_eq!(decoder.id(), b"scan");
+    assert!(decoder.color_map().is_empty());
+    assert_eq!(decoder.next_row()?, Some((0, &[10, 20, 30][..])));
+    assert_eq!(decoder.next_row()?, Some((1, &[30, 30, 30][..])));
+    assert_eq!(decoder.next_row()?, None);
+
+    // Truncated pixel data is reported when the row is read
+    let mut decoder = TgaScanlineDecoder::new(&buf[..buf.len() - 1])?;
+    assert!(matches!(decoder.next_row(), Err(TgaError::InvalidSize)));
+    Ok(())
+}
+
+#[test]
+fn scanline_encoder_round_trip() -> Result<(), TgaError> {
+    let mut image = TgaImage::new(TgaImageType::RleTrueColorImage, 4, 2, 24)?;
+    image.draw_rect(0, 0, 4, 1, TgaColor::rgb24(1, 2, 3))?;
+
+    // Rows read by the decoder can be written straight to the encoder
+    let bytes = image.to_bytes();
+    let mut decoder = TgaScanlineDecoder::new(&bytes[..])?;
+    let mut encoder = TgaScanlineEncoder::new(Vec::new(), *decoder.header())?;
+    while let Some((_, row)) = decoder.next_row()? {
+        encoder.write_row(row)?;
+    }
+    let buf = encoder.finish()?;
+    assert_eq!(TgaImage::from_bytes(&buf)?, image);
+    Ok(())
+}
+
+#[test]
+fn header_from_reader_stops_at_id() -> Result<(), TgaError> {
+    let image = TgaImage::new(TgaImageType::BlackAndWhiteImage, 5, 4, 8)?;
+    let bytes = image.to_bytes();
+
+    let mut reader = &bytes[..];
+    assert_eq!(TgaHeader::from_reader(&mut reader)?, image.header);
+    assert_eq!(reader.len(), bytes.len() - 18);
+
+    let header_reader_path = temp_path("test_header_reader.tga")?;
+    image.to_file(&header_reader_path)?;
+    assert_eq!(TgaHeader::from_file(&header_reader_path)?, image.header);
+    assert!(matches!(TgaHeader::from_reader(&mut &bytes[..17]), Err(TgaError::InvalidSize)));
+    Ok(())
+}
+
+#[test]
+fn normalize_origin_stores_top_left() -> Result<(), TgaError> {
+    // A bottom-right origin image with a unique value per pixel
+    let mut image = TgaImage::new(TgaImageType::BlackAndWhiteImage, 3, 2, 8)?;
+    image.header.descriptor |= 0x10;
+    for y in 0..2 {
+        for x in 0..3 {
+            image.set_pixel(x, y, TgaColor::Greyscale([(y * 3 + x) as u8]))?;
+        }
+    }
+    assert_eq!(image.data(), &[5, 4, 3, 2, 1, 0]);
+    let original = image.clone();
+
+    // The stored data becomes top-left ordered without changing any pixel
+    image.normalize_origin();
+    assert_eq!(image.header.descriptor_flags()?, TgaDescriptor { alpha_bits: 0, right_origin: false, top_origin: true });
+    assert_eq!(image.data(), &[0, 1, 2, 3, 4, 5]);
+    assert_eq!(image.as_rgba_vec()?, original.as_rgba_vec()?);
+
+    // Switching back restores the original data
+    image.set_right_origin(true);
+    image.set_top_origin(false);
+    assert_eq!(image, original);
+    Ok(())
+}
+
+#[test]
+fn to_file_with_origin_reorders_rows() -> Result<(), TgaError> {
+    let mut image = TgaImage::new(TgaImageType::BlackAndWhiteImage, 2, 2, 8)?;
+    image.set_pixel(0, 0, TgaColor::Greyscale([1]))?;
+    image.set_pixel(1, 1, TgaColor::Greyscale([2]))?;
+
+    let origin_top_path = temp_path("test_origin_top.tga")?;
+    // Top-left origin stores the top row first
+    image.to_file_with_origin(&origin_top_path, true)?;
+    let top = TgaImage::from_file(&origin_top_path)?;
+    assert!(top.header.descriptor_flags()?.top_origin);
+    assert_eq!(top.data(), &[1, 0, 0, 2]);
+    assert_eq!(top.as_rgba_vec()?, image.as_rgba_vec()?);
+
+    // Bottom-left origin stores the bottom row first
+    let bottom = TgaImage::from_bytes(&top.to_bytes_with_origin(false))?;
+    assert!(!bottom.header.descriptor_flags()?.top_origin);
+    assert_eq!(bottom, image);
+    Ok(())
+}
+
+#[test]
+fn decode_options_strictness() -> Result<(), TgaError> {
+    let strict = DecodeOptions { strictness: Strictness::Strict };
+    let lenient = DecodeOptions { strictness: Strictness::Lenient };
+
+    // Consistent images load the same way at every level
+    let mut image = TgaImage::new(TgaImageType::TrueColorImage, 2, 2, 24)?;
+    image.set_pixel(0, 1, TgaColor::rgb24(1, 2, 3))?;
+    let buf = image.to_bytes();
+    assert_eq!(TgaImage::from_bytes_with_options(&buf, strict)?, image);
+    assert_eq!(TgaImage::from_bytes_with_options(&buf, lenient)?, image);
+    assert_eq!(TgaImage::from_bytes_with_options(&buf, DecodeOptions::default())?, image);
+
+    // A true color image declaring an unusable 2-entry color map
+    let mut header = image.header;
+    header.has_color_map = true;
+    header.color_map_size = 2;
+    header.color_map_bit_depth = 8;
+    let mut bad = header.to_buf().to_vec();
+    bad.extend_from_slice(&[1, 2]);
+    bad.extend_from_slice(&image.data()[..9]);
+    bad[17] |= 0x40;
+
+    // Strict and standard decoding reject it, while lenient decoding drops the color map and pads the pixel data
+    assert!(matches!(TgaImage::from_bytes_with_options(&bad, strict), Err(TgaError::InvalidArgument)));
+    bad[17] &= 0x3f;
+    assert!(matches!(TgaImage::from_bytes_with_options(&bad, strict), Err(TgaError::InvalidColorMap)));
+    bad[17] |= 0x40;
+    assert!(matches!(TgaImage::from_bytes(&bad), Err(TgaError::InvalidColorMap)));
+    let repaired = TgaImage::from_bytes_with_options(&bad, lenient)?;
+    assert!(!repaired.header.has_color_map);
+    assert!(repaired.color_map.is_empty());
+    assert_eq!(repaired.header.descriptor, 0);
+    assert_eq!(repaired.data(), image.data());
+
+    // Only strict decoding rejects the descriptor's reserved bits, which legacy writers set for interleaving
+    let mut buf = image.to_bytes();
+    buf[17] |= 0x80;
+    assert!(matches!(TgaImage::from_bytes_with_options(&buf, strict), Err(TgaError::InvalidArgument)));
+    assert_eq!(TgaImage::from_bytes(&buf)?, image);
+    assert_eq!(TgaImage::from_bytes_with_options(&buf, lenient)?, image);
+
+    // Strict decoding rejects alpha bits that do not fit the bit depth
+    let mut buf = image.to_bytes();
+    buf[17] |= 0x08;
+    assert!(matches!(TgaImage::from_bytes_with_options(&buf, strict), Err(TgaError::InvalidPixelDepth)));
+    assert_eq!(TgaImage::from_bytes_with_options(&buf, lenient)?.header.alpha_bits(), 0);
+
+    // Lenient decoding does not pad a header that declares far more pixel data than the buffer holds
+    let huge = TgaHeader { image_type: TgaImageType::TrueColorImage, width: 65535, height: 65535, image_bit_depth: 32, ..TgaHeader::default() };
+    let mut buf = huge.to_buf().to_vec();
+    assert!(matches!(TgaImage::from_bytes_with_options(&buf, lenient), Err(TgaError::InvalidSize)));
+    buf.extend_from_slice(&[0x80 | 127, 1, 2, 3, 4]);
+    assert!(matches!(TgaImage::from_bytes_with_options(&buf, lenient), Err(TgaError::InvalidSize)));
+
+    let lenient_path = temp_path("test_lenient.tga")?;
+    std::fs::write(&lenient_path, &bad).unwrap();
+    assert_eq!(TgaImage::from_file_with_options(&lenient_path, lenient)?, repaired);
+    Ok(())
+}
@@ -0,0 +1,515 @@
+use crate::{TgaColor, TgaError, TgaImage, DESCRIPTOR_RIGHT, DESCRIPTOR_TOP};
+use crate::TgaError::*;
+
+/// The sampling filters used by `TgaImage::resize`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResizeFilter {
+    /// Copies the nearest source pixel, as in `resize_nearest`.
+    Nearest,
+    /// Interpolates the four nearest source pixels, as in `resize_bilinear`.
+    Bilinear,
+    /// Averages every source pixel covered by the destination pixel, as in `downscale_average`.
+    Box,
+}
+
+impl TgaImage {
+    /// Tries to create a copy of this image resized to `new_width`x`new_height` with `filter`.
+    /// 
+    /// This dispatches to `resize_nearest`, `resize_bilinear`, or `downscale_average`, and supports the same pixel formats as the chosen method. The `Box` filter can only shrink an image.
+    /// 
+    /// # Errors
+    /// Returns the same errors as the method used for `filter`.
+    pub fn resize(&self, new_width: u16, new_height: u16, filter: ResizeFilter) -> Result<TgaImage, TgaError> {
+        match filter {
+            ResizeFilter::Nearest => self.resize_nearest(new_width, new_height),
+            ResizeFilter::Bilinear => self.resize_bilinear(new_width, new_height),
+            ResizeFilter::Box => self.downscale_average(new_width, new_height)
+        }
+    }
+
+    /// Mirrors the image horizontally in place.
+    pub fn mirror(&mut self) {
+        let byte_depth = self.byte_depth();
+        let row_size = self.row_size();
+        if row_size == 0 {
+            return;
+        }
+
+        // Reverse each row, then restore the channel order within each pixel
+        for row in self.data.chunks_exact_mut(row_size) {
+            row.reverse();
+            if byte_depth > 1 {
+                for pixel in row.chunks_exact_mut(byte_depth) {
+                    pixel.reverse();
+                }
+            }
+        }
+    }
+
+    /// Rotates the image by 180 degrees in place.
+    /// 
+    /// This is equivalent to flipping the image both horizontally and vertically, but only makes a single pass over the pixel data.
+    pub fn rotate180(&mut self) {
+        // Reversing the bytes reverses the pixel order, but also the channel order within each pixel
+        let byte_depth = self.byte_depth();
+        self.data.reverse();
+        if byte_depth > 1 {
+            for pixel in self.data.chunks_exact_mut(byte_depth) {
+                pixel.reverse();
+            }
+        }
+    }
+
+    /// Sets whether the image's rows are stored from top to bottom.
+    /// 
+    /// This sets or clears bit 5 of the descriptor and reverses the order of the stored rows if it changes, so the image looks the same when displayed. Since pixel coordinates follow the origin bits, every pixel keeps its coordinate.
+    pub fn set_top_origin(&mut self, top: bool) {
+        if (self.header.descriptor & DESCRIPTOR_TOP != 0) == top {
+            return;
+        }
+        self.header.descriptor ^= DESCRIPTOR_TOP;
+
+        // Reverse the row order
+        let row_size = self.row_size();
+        let height = self.header.height as usize;
+        if row_size == 0 {
+            return;
+        }
+        for row in 0..height / 2 {
+            let (first, second) = self.data.split_at_mut((height - 1 - row) * row_size);
+            first[row * row_size..(row + 1) * row_size].swap_with_slice(&mut second[..row_size]);
+        }
+    }
+
+    /// Sets whether the image's pixels are stored from right to left.
+    /// 
+    /// This sets or clears bit 4 of the descriptor and reverses the pixels of each stored row if it changes, so the image looks the same when displayed. Since pixel coordinates follow the origin bits, every pixel keeps its coordinate.
+    pub fn set_right_origin(&mut self, right: bool) {
+        if (self.header.descriptor & DESCRIPTOR_RIGHT != 0) == right {
+            return;
+        }
+        self.header.descriptor ^= DESCRIPTOR_RIGHT;
+        self.mirror();
+    }
+
+    /// Reorders the pixel data so it is stored from the top-left corner.
+    /// 
+    /// After this, `data` starts with the top row and each row starts with its leftmost pixel, whatever origin the image was loaded with. The image looks the same when displayed.
+    pub fn normalize_origin(&mut self) {
+        self.set_top_origin(true);
+        self.set_right_origin(false);
+    }
+
+    /// Tries to create a copy of this image resized with bilinear interpolation.
+    /// 
+    /// Each destination pixel is interpolated from the four nearest source pixels, with pixel centers aligned between the images. The alpha channel of 32-bit images is interpolated like the color channels, without premultiplying.
+    /// 
+    /// # Errors
+    /// If `new_width` or `new_height` is zero, returns `InvalidSize` error.
+    /// 
+    /// If the image is not an 8-bit greyscale or 24/32-bit true color image, returns `InvalidImageType` error.
+    pub fn resize_bilinear(&self, new_width: u16, new_height: u16) -> Result<TgaImage, TgaError> {
+        if new_width == 0 || new_height == 0 {
+            return Err(InvalidSize);
+        }
+        self.color_channels()?;
+        if self.header.width == 0 || self.header.height == 0 {
+            return Err(InvalidSize);
+        }
+
+        // Maps a destination coordinate to the two nearest source coordinates and the weight of the second
+        fn sample(dest: u16, dest_size: u16, src_size: u16) -> (u16, u16, f32) {
+            let scale = src_size as f32 / dest_size as f32;
+            let src = ((dest as f32 + 0.5) * scale - 0.5).clamp(0.0, (src_size - 1) as f32);
+            let first = src.floor() as u16;
+            let second = (first + 1).min(src_size - 1);
+            (first, second, src - first as f32)
+        }
+
+        let byte_depth = self.byte_depth();
+        let mut image = self.with_size(new_width, new_height, vec![0; new_width as usize * new_height as usize * byte_depth]);
+        for y in 0..new_height {
+            let (y0, y1, fy) = sample(y, new_height, self.header.height);
+            for x in 0..new_width {
+                let (x0, x1, fx) = sample(x, new_width, self.header.width);
+                let p00 = self.pixel_offset(x0, y0);
+                let p10 = self.pixel_offset(x1, y0);
+                let p01 = self.pixel_offset(x0, y1);
+                let p11 = self.pixel_offset(x1, y1);
+                let dest = image.pixel_offset(x, y);
+                for channel in 0..byte_depth {
+                    let top = lerp(self.data[p00 + channel], self.data[p10 + channel], fx);
+                    let bottom = lerp(self.data[p01 + channel], self.data[p11 + channel], fx);
+                    image.data[dest + channel] = (top + (bottom - top) * fy).round().clamp(0.0, 255.0) as u8;
+                }
+            }
+        }
+
+        Ok(image)
+    }
+
+    /// Tries to create a smaller copy of this image by averaging the source pixels that map to each destination pixel.
+    /// 
+    /// Each destination pixel is the rounded average of its source region, with every channel including alpha averaged separately. Use `resize_bilinear` to enlarge an image.
+    /// 
+    /// # Errors
+    /// If `new_width` or `new_height` is zero or larger than the image's size, returns `InvalidSize` error.
+    /// 
+    /// If the image is not an 8-bit greyscale or 24/32-bit true color image, returns `InvalidImageType` error.
+    pub fn downscale_average(&self, new_width: u16, new_height: u16) -> Result<TgaImage, TgaError> {
+        if new_width == 0 || new_height == 0 || new_width > self.header.width || new_height > self.header.height {
+            return Err(InvalidSize);
+        }
+        self.color_channels()?;
+
+        // Maps a destination coordinate to the range of source coordinates it covers
+        fn region(dest: u16, dest_size: u16, src_size: u16) -> std::ops::Range<u16> {
+            let start = dest as u32 * src_size as u32 / dest_size as u32;
+            let end = (dest as u32 + 1) * src_size as u32 / dest_size as u32;
+            start as u16..end as u16
+        }
+
+        let byte_depth = self.byte_depth();
+        let mut image = self.with_size(new_width, new_height, vec![0; new_width as usize * new_height as usize * byte_depth]);
+        let mut sums = vec![0u32; byte_depth];
+        for y in 0..new_height {
+            let rows = region(y, new_height, self.header.height);
+            for x in 0..new_width {
+                let columns = region(x, new_width, self.header.width);
+
+                // Sum each channel over the source region
+                sums.iter_mut().for_each(|sum| *sum = 0);
+                for sy in rows.clone() {
+                    for sx in columns.clone() {
+                        let src = self.pixel_offset(sx, sy);
+                        for (sum, &value) in sums.iter_mut().zip(&self.data[src..src + byte_depth]) {
+                            *sum += value as u32;
+                        }
+                    }
+                }
+
+                let count = rows.len() as u32 * columns.len() as u32;
+                let dest = image.pixel_offset(x, y);
+                for (channel, sum) in sums.iter().enumerate() {
+                    image.data[dest + channel] = ((sum + count / 2) / count) as u8;
+                }
+            }
+        }
+
+        Ok(image)
+    }
+
+    /// Tries to create a copy of this image resized with nearest-neighbor sampling.
+    /// 
+    /// Each destination pixel copies the source pixel whose center is nearest, so every pixel format is supported.
+    /// 
+    /// # Errors
+    /// If `new_width`, `new_height`, or the image's width or height is zero, returns `InvalidSize` error.
+    pub fn resize_nearest(&self, new_width: u16, new_height: u16) -> Result<TgaImage, TgaError> {
+        if new_width == 0 || new_height == 0 || self.header.width == 0 || self.header.height == 0 {
+            return Err(InvalidSize);
+        }
+
+        // Maps a destination coordinate to the nearest source coordinate
+        fn sample(dest: u16, dest_size: u16, src_size: u16) -> u16 {
+            ((dest as u32 * 2 + 1) * src_size as u32 / (dest_size as u32 * 2)) as u16
+        }
+
+        let byte_depth = self.byte_depth();
+        let mut image = self.with_size(new_width, new_height, vec![0; new_width as usize * new_height as usize * byte_depth]);
+        for y in 0..new_height {
+            let sy = sample(y, new_height, self.header.height);
+            for x in 0..new_width {
+                let src = self.pixel_offset(sample(x, new_width, self.header.width), sy);
+                let dest = image.pixel_offset(x, y);
+                image.data[dest..dest + byte_depth].copy_from_slice(&self.data[src..src + byte_depth]);
+            }
+        }
+
+        Ok(image)
+    }
+
+    /// Tries to create a thumbnail whose longest side is `max_dim` pixels.
+    /// 
+    /// The aspect ratio is preserved, with the shorter side rounded to the nearest pixel but at least 1. The image is resized with `resize_nearest`.
+    /// 
+    /// # Errors
+    /// If `max_dim` or the image's width or height is zero, returns `InvalidSize` error.
+    pub fn thumbnail(&self, max_dim: u16) -> Result<TgaImage, TgaError> {
+        let width = self.header.width as u32;
+        let height = self.header.height as u32;
+        if max_dim == 0 || width == 0 || height == 0 {
+            return Err(InvalidSize);
+        }
+
+        // Scale the shorter side by the same ratio as the longest side
+        let longest = width.max(height);
+        let scale = |side: u32| {((side * max_dim as u32 * 2 + longest) / (longest * 2)).max(1) as u16};
+        self.resize_nearest(scale(width), scale(height))
+    }
+
+    /// Tries to create a copy of this image rotated clockwise by `degrees` around its center.
+    /// 
+    /// The canvas is enlarged to fit the rotated image, and pixels outside of the rotated image are set to `fill`. Each destination pixel is sampled from the nearest source pixel.
+    /// 
+    /// # Errors
+    /// If the image is color-mapped, returns `InvalidImageType` error.
+    /// 
+    /// If `degrees` is not a finite number, returns `InvalidArgument` error.
+    /// 
+    /// If `fill` is invalid for this image, returns `InvalidColor` error.
+    /// 
+    /// If `fill`'s bit depth does not match this image, returns `InvalidPixelDepth` error.
+    /// 
+    /// If the rotated image is too large for a TGA image, returns `InvalidSize` error.
+    pub fn rotate(&self, degrees: f32, fill: TgaColor) -> Result<TgaImage, TgaError> {
+        if self.header.image_type.is_color_mapped() {
+            return Err(InvalidImageType);
+        }
+        if !degrees.is_finite() {
+            return Err(InvalidArgument);
+        }
+        self.check_color(fill)?;
+
+        // Find the bounds of the rotated image, ignoring rounding error
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let width = self.header.width as f32;
+        let height = self.header.height as f32;
+        let bound = |size: f32| {(size - 1e-3).ceil().max(1.0)};
+        let new_width = bound(width * cos.abs() + height * sin.abs());
+        let new_height = bound(width * sin.abs() + height * cos.abs());
+        if new_width > u16::MAX as f32 || new_height > u16::MAX as f32 {
+            return Err(InvalidSize);
+        }
+        let (new_width, new_height) = (new_width as u16, new_height as u16);
+
+        // Map each destination pixel center back into the source image
+        let byte_depth = self.byte_depth();
+        let mut image = self.with_size(new_width, new_height, fill.as_slice().repeat(new_width as usize * new_height as usize));
+        for y in 0..new_height {
+            let dy = y as f32 + 0.5 - new_height as f32 / 2.0;
+            for x in 0..new_width {
+                let dx = x as f32 + 0.5 - new_width as f32 / 2.0;
+                let sx = (cos * dx + sin * dy + width / 2.0).floor();
+                let sy = (cos * dy - sin * dx + height / 2.0).floor();
+                if sx < 0.0 || sy < 0.0 || sx >= width || sy >= height {
+                    continue;
+                }
+
+                let src = self.pixel_offset(sx as u16, sy as u16);
+                let dest = image.pixel_offset(x, y);
+                image.data[dest..dest + byte_depth].copy_from_slice(&self.data[src..src + byte_depth]);
+            }
+        }
+
+        Ok(image)
+    }
+
+    /// Tries to create a copy of this image with a different canvas size, without scaling.
+    /// 
+    /// Pixels keep their coordinates, so the canvas is anchored at (0, 0). Pixels outside of the new canvas are cropped, and newly exposed pixels are set to `fill`.
+    /// 
+    /// # Errors
+    /// If `new_width` or `new_height` is zero, returns `InvalidSize` error.
+    /// 
+    /// If `fill` is invalid for this image, returns `InvalidColor` error.
+    /// 
+    /// If `fill`'s bit depth does not match this image, returns `InvalidPixelDepth` error.
+    pub fn resize_canvas(&self, new_width: u16, new_height: u16, fill: TgaColor) -> Result<TgaImage, TgaError> {
+        if new_width == 0 || new_height == 0 {
+            return Err(InvalidSize);
+        }
+        self.check_color(fill)?;
+
+        // Fill the new canvas, then copy the overlapping pixels
+        let data = fill.as_slice().repeat(new_width as usize * new_height as usize);
+        let mut image = self.with_size(new_width, new_height, data);
+        let byte_depth = self.byte_depth();
+        for y in 0..self.header.height.min(new_height) {
+            for x in 0..self.header.width.min(new_width) {
+                let src = self.pixel_offset(x, y);
+                let dest = image.pixel_offset(x, y);
+                image.data[dest..dest + byte_depth].copy_from_slice(&self.data[src..src + byte_depth]);
+            }
+        }
+
+        Ok(image)
+    }
+
+    /// Tries to create a copy of the `w`x`h` rectangle at (`x`, `y`).
+    /// 
+    /// # Errors
+    /// If `w` or `h` is zero, returns `InvalidSize` error.
+    /// 
+    /// If the rectangle is not fully inside the image, returns `InvalidCoordinate` error.
+    pub fn crop(&self, x: u16, y: u16, w: u16, h: u16) -> Result<TgaImage, TgaError> {
+        if w == 0 || h == 0 {
+            return Err(InvalidSize);
+        }
+        self.check_rect(x, y, w, h)?;
+
+        let byte_depth = self.byte_depth();
+        let mut image = self.with_size(w, h, vec![0; w as usize * h as usize * byte_depth]);
+        for dy in 0..h {
+            for dx in 0..w {
+                let src = self.pixel_offset(x + dx, y + dy);
+                let dest = image.pixel_offset(dx, dy);
+                image.data[dest..dest + byte_depth].copy_from_slice(&self.data[src..src + byte_depth]);
+            }
+        }
+
+        Ok(image)
+    }
+
+    /// Tries to crop away the rows and columns around the image that only contain `border_color`.
+    /// 
+    /// Returns the smallest crop containing every pixel that is not `border_color`.
+    /// 
+    /// # Errors
+    /// If `border_color` is invalid for this image, returns `InvalidColor` error.
+    /// 
+    /// If `border_color`'s bit depth does not match this image, returns `InvalidPixelDepth` error.
+    /// 
+    /// If every pixel is `border_color`, returns `InvalidSize` error.
+    pub fn crop_to_content(&self, border_color: TgaColor) -> Result<TgaImage, TgaError> {
+        self.check_color(border_color)?;
+
+        // Find the bounding box of the pixels that differ from the border
+        let byte_depth = self.byte_depth();
+        let mut bounds: Option<(u16, u16, u16, u16)> = None;
+        for y in 0..self.header.height {
+            for x in 0..self.header.width {
+                let start = self.pixel_offset(x, y);
+                if &self.data[start..start + byte_depth] == border_color.as_slice() {
+                    continue;
+                }
+                bounds = Some(match bounds {
+                    Some((min_x, min_y, max_x, max_y)) => (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y)),
+                    None => (x, y, x, y)
+                });
+            }
+        }
+
+        let (min_x, min_y, max_x, max_y) = bounds.ok_or(InvalidSize)?;
+        self.crop(min_x, min_y, max_x - min_x + 1, max_y - min_y + 1)
+    }
+
+    /// Tries to join `images` side by side from left to right.
+    /// 
+    /// The result has the format, origin, id, and color map of the first image, and its width is the sum of the images' widths.
+    /// 
+    /// # Errors
+    /// If `images` is empty, the images' heights differ, or the total width does not fit in a `u16`, returns `InvalidSize` error.
+    /// 
+    /// If the images' types or bit depths differ, returns `InvalidImageType` error.
+    /// 
+    /// If the images are color-mapped and their color maps differ, returns `InvalidColorMap` error.
+    pub fn concat_horizontal(images: &[TgaImage]) -> Result<TgaImage, TgaError> {
+        TgaImage::concat(images, true)
+    }
+
+    /// Tries to join `images` from top to bottom.
+    /// 
+    /// The result has the format, origin, id, and color map of the first image, and its height is the sum of the images' heights.
+    /// 
+    /// # Errors
+    /// If `images` is empty, the images' widths differ, or the total height does not fit in a `u16`, returns `InvalidSize` error.
+    /// 
+    /// If the images' types or bit depths differ, returns `InvalidImageType` error.
+    /// 
+    /// If the images are color-mapped and their color maps differ, returns `InvalidColorMap` error.
+    pub fn concat_vertical(images: &[TgaImage]) -> Result<TgaImage, TgaError> {
+        TgaImage::concat(images, false)
+    }
+
+    /// Tries to join `images` along the horizontal axis if `horizontal` is true, or the vertical axis otherwise.
+    fn concat(images: &[TgaImage], horizontal: bool) -> Result<TgaImage, TgaError> {
+        let first = images.first().ok_or(InvalidSize)?;
+
+        // Ensure the images match and sum their sizes along the axis
+        let mut length: u16 = 0;
+        for image in images {
+            if image.header.image_type != first.header.image_type || image.header.image_bit_depth != first.header.image_bit_depth {
+                return Err(InvalidImageType);
+            }
+            if image.header.image_type.is_color_mapped() && !image.same_color_map(first) {
+                return Err(InvalidColorMap);
+            }
+            let (along, across, first_across) = if horizontal {
+                (image.header.width, image.header.height, first.header.height)
+            } else {
+                (image.header.height, image.header.width, first.header.width)
+            };
+            if across != first_across {
+                return Err(InvalidSize);
+            }
+            length = length.checked_add(along).ok_or(InvalidSize)?;
+        }
+
+        let (width, height) = if horizontal { (length, first.header.height) } else { (first.header.width, length) };
+        let byte_depth = first.byte_depth();
+        let mut result = first.with_size(width, height, vec![0; width as usize * height as usize * byte_depth]);
+
+        // Copy each image after the previous one
+        let mut offset = 0;
+        for image in images {
+            for y in 0..image.header.height {
+                for x in 0..image.header.width {
+                    let src = image.pixel_offset(x, y);
+                    let dest = if horizontal { result.pixel_offset(offset + x, y) } else { result.pixel_offset(x, offset + y) };
+                    result.data[dest..dest + byte_depth].copy_from_slice(&image.data[src..src + byte_depth]);
+                }
+            }
+            offset += if horizontal { image.header.width } else { image.header.height };
+        }
+
+        Ok(result)
+    }
+
+    /// Tries to find the bounding box of the pixels with a nonzero alpha.
+    /// 
+    /// Returns the `(x, y, w, h)` rectangle that `crop` would take, or `None` if every pixel is fully transparent.
+    /// 
+    /// # Errors
+    /// If the image is not a 32-bit true color image, returns `InvalidImageType` error.
+    pub fn alpha_bounds(&self) -> Result<Option<(u16, u16, u16, u16)>, TgaError> {
+        self.check_rgba()?;
+
+        let mut bounds: Option<(u16, u16, u16, u16)> = None;
+        for y in 0..self.header.height {
+            for x in 0..self.header.width {
+                if self.data[self.pixel_offset(x, y) + 3] == 0 {
+                    continue;
+                }
+                bounds = Some(match bounds {
+                    Some((min_x, min_y, max_x, max_y)) => (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y)),
+                    None => (x, y, x, y)
+                });
+            }
+        }
+
+        Ok(bounds.map(|(min_x, min_y, max_x, max_y)| (min_x, min_y, max_x - min_x + 1, max_y - min_y + 1)))
+    }
+
+    /// Returns a copy of this image with new dimensions and pixel data of the same format.
+    fn with_size(&self, width: u16, height: u16, data: Vec<u8>) -> TgaImage {
+        let mut header = self.header;
+        header.width = width;
+        header.height = height;
+
+        TgaImage {
+            header,
+            state: self.state,
+            id: self.id.clone(),
+            color_map: self.color_map.clone(),
+            data: data.into_boxed_slice(),
+            developer_tags: self.developer_tags.clone(),
+            extension: self.extension.clone()
+        }
+    }
+}
+
+/// Linearly interpolates between `a` and `b`.
+fn lerp(a: u8, b: u8, t: f32) -> f32 {
+    a as f32 + (b as f32 - a as f32) * t
+}